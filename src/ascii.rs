@@ -1,56 +1,590 @@
-use bevy::prelude::*;
+use std::{collections::HashMap, error::Error, fmt, fs, io, str::FromStr};
+
+use bevy::{asset::io::file::FileAssetReader, prelude::*};
+use serde::Deserialize;
+
+use crate::{
+    assets::ASSETS_PATH,
+    entities::{GhostType, MovementDirection},
+};
 
 #[derive(Resource)]
 pub struct AsciiSheet {
     pub image: Handle<Image>,
     pub layout: Handle<TextureAtlasLayout>,
+    pub atlas: SpriteAtlas,
+}
+
+impl AsciiSheet {
+    /// Index of the given sprite on the sprite sheet.
+    pub fn index(&self, sprite: SpriteIndices) -> usize {
+        self.atlas.index(sprite)
+    }
 }
 
-/// A struct representing the indices of the sprites on the sprite sheet.
-#[derive(Debug, PartialEq, Eq)]
+/// The sprites on the sprite sheet the game uses. Their indices are given by the atlas
+/// description (see [`SpriteAtlas`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SpriteIndices {
-    PacmanClosed = 0,
-    PacmanOpen = 1,
-    Blinky1 = 2,
-    Blinky2 = 3,
-    Inky1 = 4,
-    Inky2 = 5,
-    Pinky1 = 6,
-    Pinky2 = 7,
-    Clyde1 = 8,
-    Clyde2 = 9,
-    WallCorner = 10,
-    WallStraight = 11,
-    SmallCoin = 12,
-    LargeCoin = 13,
-    Empty = 14,
-}
-
-impl From<SpriteIndices> for usize {
-    fn from(val: SpriteIndices) -> Self {
-        val as usize
-    }
-}
-
-/// Load the sprite sheet (aka, TextureAtlas) from the assets.
+    PacmanClosed,
+    Blinky1,
+    Inky1,
+    Pinky1,
+    Clyde1,
+    WallCorner,
+    WallStraight,
+    SmallCoin,
+    LargeCoin,
+    Empty,
+    DoubleWallCorner,
+    DoubleWallStraight,
+    HouseWallCorner,
+    HouseWallStraight,
+    SlowTile,
+    OneWayArrow,
+    NoGhostsTile,
+    /// A ghost, which can be eaten by pacman.
+    Frightened1,
+    /// The eyes of a ghost looking in the given direction. They are drawn on top of the ghosts
+    /// and on their own, when a ghost was eaten.
+    Eyes(MovementDirection),
+    Cherry,
+    Strawberry,
+    Orange,
+    Apple,
+    /// A digit from 0 to 9, e.g., for showing points. Other numbers panic.
+    Digit(u8),
+    PacmanDeath1,
+}
+
+impl SpriteIndices {
+    pub const ALL: [Self; 37] = [
+        Self::PacmanClosed,
+        Self::Blinky1,
+        Self::Inky1,
+        Self::Pinky1,
+        Self::Clyde1,
+        Self::WallCorner,
+        Self::WallStraight,
+        Self::SmallCoin,
+        Self::LargeCoin,
+        Self::Empty,
+        Self::DoubleWallCorner,
+        Self::DoubleWallStraight,
+        Self::HouseWallCorner,
+        Self::HouseWallStraight,
+        Self::SlowTile,
+        Self::OneWayArrow,
+        Self::NoGhostsTile,
+        Self::Frightened1,
+        Self::Eyes(MovementDirection::Up),
+        Self::Eyes(MovementDirection::Right),
+        Self::Eyes(MovementDirection::Down),
+        Self::Eyes(MovementDirection::Left),
+        Self::Cherry,
+        Self::Strawberry,
+        Self::Orange,
+        Self::Apple,
+        Self::Digit(0),
+        Self::Digit(1),
+        Self::Digit(2),
+        Self::Digit(3),
+        Self::Digit(4),
+        Self::Digit(5),
+        Self::Digit(6),
+        Self::Digit(7),
+        Self::Digit(8),
+        Self::Digit(9),
+        Self::PacmanDeath1,
+    ];
+
+    /// Name of the sprite in the atlas description.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::PacmanClosed => "pacman_closed",
+            Self::Blinky1 => "blinky_1",
+            Self::Inky1 => "inky_1",
+            Self::Pinky1 => "pinky_1",
+            Self::Clyde1 => "clyde_1",
+            Self::WallCorner => "wall_corner",
+            Self::WallStraight => "wall_straight",
+            Self::SmallCoin => "small_coin",
+            Self::LargeCoin => "large_coin",
+            Self::Empty => "empty",
+            Self::DoubleWallCorner => "double_wall_corner",
+            Self::DoubleWallStraight => "double_wall_straight",
+            Self::HouseWallCorner => "house_wall_corner",
+            Self::HouseWallStraight => "house_wall_straight",
+            Self::SlowTile => "slow_tile",
+            Self::OneWayArrow => "one_way_arrow",
+            Self::NoGhostsTile => "no_ghosts_tile",
+            Self::Frightened1 => "frightened_1",
+            Self::Eyes(MovementDirection::Up) => "eyes_up",
+            Self::Eyes(MovementDirection::Right) => "eyes_right",
+            Self::Eyes(MovementDirection::Down) => "eyes_down",
+            Self::Eyes(MovementDirection::Left) => "eyes_left",
+            Self::Cherry => "cherry",
+            Self::Strawberry => "strawberry",
+            Self::Orange => "orange",
+            Self::Apple => "apple",
+            Self::Digit(digit) => match DIGITS.get(digit as usize) {
+                Some(name) => name,
+                None => panic!("{digit} is not a single digit"),
+            },
+            Self::PacmanDeath1 => "pacman_death_1",
+        }
+    }
+}
+
+const DIGITS: [&str; 10] = [
+    "digit_0", "digit_1", "digit_2", "digit_3", "digit_4", "digit_5", "digit_6", "digit_7",
+    "digit_8", "digit_9",
+];
+
+/// The animations on the sprite sheet the game uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Animation {
+    Pacman,
+    Ghost(GhostType),
+    /// A ghost, which can be eaten by pacman.
+    Frightened,
+    /// A frightened ghost flashing, shortly before it stops being frightened.
+    FrightenedFlashing,
+    /// Pacman shrinking after being caught by a ghost. It should not be repeated.
+    PacmanDeath,
+}
+
+impl Animation {
+    pub const ALL: [Self; 8] = [
+        Self::Pacman,
+        Self::Ghost(GhostType::Blinky),
+        Self::Ghost(GhostType::Inky),
+        Self::Ghost(GhostType::Pinky),
+        Self::Ghost(GhostType::Clyde),
+        Self::Frightened,
+        Self::FrightenedFlashing,
+        Self::PacmanDeath,
+    ];
+
+    /// Name of the animation in the atlas description.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Pacman => "pacman",
+            Self::Ghost(GhostType::Blinky) => "blinky",
+            Self::Ghost(GhostType::Inky) => "inky",
+            Self::Ghost(GhostType::Pinky) => "pinky",
+            Self::Ghost(GhostType::Clyde) => "clyde",
+            Self::Frightened => "frightened",
+            Self::FrightenedFlashing => "frightened_flashing",
+            Self::PacmanDeath => "pacman_death",
+        }
+    }
+}
+
+/// Path of the atlas description, relative to the assets folder.
+pub const ATLAS_PATH: &str = "ascii.ron";
+
+/// The atlas description compiled into the binary, used if there is no `ascii.ron` in the assets
+/// folder.
+pub const ASCII_ATLAS: &str = include_str!("../assets/ascii.ron");
+
+/// The sprite sheet compiled into the binary, used if there is no `ascii.png` in the assets folder.
+pub const ASCII_SHEET: &[u8] = include_bytes!("../assets/ascii.png");
+
+/// Error returned when the atlas description is invalid or can not be read.
+#[derive(Debug)]
+pub enum AtlasError {
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+    /// A sprite the game needs is not named in the description.
+    MissingSprite(&'static str),
+    /// An animation the game needs is not described.
+    MissingAnimation(&'static str),
+    /// The index of a sprite lies outside of the grid of the sprite sheet.
+    IndexOutOfRange {
+        sprite: String,
+        index: usize,
+    },
+    /// An animation does not have any frames.
+    EmptyAnimation(String),
+    /// A frame of an animation is not a named sprite.
+    UnknownFrame {
+        animation: String,
+        sprite: String,
+    },
+}
+
+impl fmt::Display for AtlasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AtlasError::Io(e) => write!(f, "could not read sprite atlas: {e}"),
+            AtlasError::Parse(e) => write!(f, "invalid sprite atlas: {e}"),
+            AtlasError::MissingSprite(name) => {
+                write!(f, "sprite atlas does not name the sprite '{name}'")
+            }
+            AtlasError::MissingAnimation(name) => {
+                write!(f, "sprite atlas does not describe the animation '{name}'")
+            }
+            AtlasError::IndexOutOfRange { sprite, index } => write!(
+                f,
+                "index {index} of sprite '{sprite}' lies outside of the sprite sheet"
+            ),
+            AtlasError::EmptyAnimation(name) => write!(f, "animation '{name}' has no frames"),
+            AtlasError::UnknownFrame { animation, sprite } => write!(
+                f,
+                "animation '{animation}' uses the sprite '{sprite}', which is not named"
+            ),
+        }
+    }
+}
+
+impl Error for AtlasError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AtlasError::Io(e) => Some(e),
+            AtlasError::Parse(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for AtlasError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<ron::error::SpannedError> for AtlasError {
+    fn from(value: ron::error::SpannedError) -> Self {
+        Self::Parse(value)
+    }
+}
+
+/// The atlas description as written in the file.
+#[derive(Deserialize)]
+struct AtlasFile {
+    image: String,
+    sprite_size: (u32, u32),
+    columns: u32,
+    rows: u32,
+    sprites: HashMap<String, usize>,
+    animations: HashMap<String, Vec<String>>,
+}
+
+/// Description of the sprite sheet: its grid and where every sprite and animation is on it. It
+/// is read from `ascii.ron`, so the sprite sheet can be changed without touching the code, e.g.:
+///
+/// ```text
+/// (
+///     image: "ascii.png",
+///     sprite_size: (32, 32),
+///     columns: 4,
+///     rows: 14,
+///     sprites: { "pacman_closed": 0, "pacman_open": 1, ... },
+///     animations: { "pacman": ["pacman_closed", "pacman_open"], ... },
+/// )
+/// ```
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct SpriteAtlas {
+    /// Path of the sprite sheet, relative to the assets folder.
+    pub image: String,
+    pub sprite_size: UVec2,
+    pub columns: u32,
+    pub rows: u32,
+    sprites: HashMap<String, usize>,
+    animations: HashMap<String, Vec<usize>>,
+}
+
+impl FromStr for SpriteAtlas {
+    type Err = AtlasError;
+
+    /// Parse the atlas description and check, that all sprites and animations needed by the
+    /// game are there.
+    fn from_str(content: &str) -> Result<Self, Self::Err> {
+        let file = ron::from_str::<AtlasFile>(content)?;
+        let count = (file.columns * file.rows) as usize;
+
+        if let Some((sprite, index)) = file.sprites.iter().find(|(_, index)| **index >= count) {
+            return Err(AtlasError::IndexOutOfRange {
+                sprite: sprite.clone(),
+                index: *index,
+            });
+        }
+        if let Some(sprite) = SpriteIndices::ALL
+            .into_iter()
+            .find(|sprite| !file.sprites.contains_key(sprite.name()))
+        {
+            return Err(AtlasError::MissingSprite(sprite.name()));
+        }
+        if let Some(animation) = Animation::ALL
+            .into_iter()
+            .find(|animation| !file.animations.contains_key(animation.name()))
+        {
+            return Err(AtlasError::MissingAnimation(animation.name()));
+        }
+
+        let mut animations = HashMap::new();
+        for (animation, frames) in file.animations {
+            if frames.is_empty() {
+                return Err(AtlasError::EmptyAnimation(animation));
+            }
+            let frames = frames
+                .into_iter()
+                .map(|sprite| {
+                    file.sprites
+                        .get(&sprite)
+                        .copied()
+                        .ok_or_else(|| AtlasError::UnknownFrame {
+                            animation: animation.clone(),
+                            sprite,
+                        })
+                })
+                .collect::<Result<_, _>>()?;
+            animations.insert(animation, frames);
+        }
+
+        Ok(Self {
+            image: file.image,
+            sprite_size: file.sprite_size.into(),
+            columns: file.columns,
+            rows: file.rows,
+            sprites: file.sprites,
+            animations,
+        })
+    }
+}
+
+impl SpriteAtlas {
+    /// Read the atlas description from the assets folder, falling back to the one compiled into
+    /// the binary.
+    pub fn load() -> Result<Self, AtlasError> {
+        let path = FileAssetReader::get_base_path()
+            .join(ASSETS_PATH)
+            .join(ATLAS_PATH);
+
+        match fs::read_to_string(path) {
+            Ok(content) => content.parse(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => ASCII_ATLAS.parse(),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// The atlas description compiled into the binary.
+    pub fn builtin() -> Self {
+        ASCII_ATLAS
+            .parse()
+            .expect("the built-in sprite atlas should be valid")
+    }
+
+    /// Index of the given sprite on the sprite sheet.
+    ///
+    /// Panics, if the sprite is missing, which can only happen if it is not listed in
+    /// [`SpriteIndices::ALL`], since those are checked when the description is parsed.
+    pub fn index(&self, sprite: SpriteIndices) -> usize {
+        match self.sprites.get(sprite.name()) {
+            Some(index) => *index,
+            None => panic!("sprite '{}' is missing in the sprite atlas", sprite.name()),
+        }
+    }
+
+    /// Indices of all frames of the given animation.
+    ///
+    /// Panics, if the animation is missing (see [`SpriteAtlas::index`]).
+    pub fn frames(&self, animation: Animation) -> &[usize] {
+        match self.animations.get(animation.name()) {
+            Some(frames) => frames,
+            None => panic!(
+                "animation '{}' is missing in the sprite atlas",
+                animation.name()
+            ),
+        }
+    }
+
+    /// Index of the frame shown after the given one. Animations start over after their last
+    /// frame, and at their first frame if the given one is not part of them.
+    pub fn next_frame(&self, animation: Animation, current: usize) -> usize {
+        let frames = self.frames(animation);
+        let next = frames
+            .iter()
+            .position(|frame| *frame == current)
+            .map_or(0, |position| (position + 1) % frames.len());
+        frames.get(next).copied().unwrap_or(current)
+    }
+
+    /// Layout of the sprites on the sprite sheet.
+    pub fn layout(&self) -> TextureAtlasLayout {
+        TextureAtlasLayout::from_grid(
+            self.sprite_size,
+            self.columns,
+            self.rows,
+            Some(UVec2::splat(0)),
+            Some(UVec2::default()),
+        )
+    }
+}
+
+/// Load the sprite sheet (aka, TextureAtlas) from the assets, as described by the atlas.
 pub fn load_ascii(
     mut commands: Commands,
     assets: Res<AssetServer>,
+    atlas: Res<SpriteAtlas>,
     mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
 ) {
-    let image = assets.load("ascii.png");
-    let atlas_layout = TextureAtlasLayout::from_grid(
-        UVec2::splat(32), // 32x32px per sprite
-        4,
-        4,
-        Some(UVec2::splat(0)),
-        Some(UVec2::default()),
-    );
-
-    let layout_handle = texture_atlases.add(atlas_layout);
+    let image = assets.load(atlas.image.clone());
+    let layout_handle = texture_atlases.add(atlas.layout());
 
     commands.insert_resource(AsciiSheet {
         image,
         layout: layout_handle,
+        atlas: atlas.clone(),
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::{
+        ascii::{Animation, AtlasError, SpriteAtlas, SpriteIndices, ASCII_ATLAS},
+        entities::{GhostType, MovementDirection},
+    };
+
+    #[test]
+    fn test_all_sprites_are_listed() {
+        // a new variant fails to compile here, until it gets its own number, and is only counted
+        // if it is listed in `ALL`
+        let variants = SpriteIndices::ALL
+            .iter()
+            .map(|sprite| match sprite {
+                SpriteIndices::PacmanClosed => 0,
+                SpriteIndices::Blinky1 => 1,
+                SpriteIndices::Inky1 => 2,
+                SpriteIndices::Pinky1 => 3,
+                SpriteIndices::Clyde1 => 4,
+                SpriteIndices::WallCorner => 5,
+                SpriteIndices::WallStraight => 6,
+                SpriteIndices::SmallCoin => 7,
+                SpriteIndices::LargeCoin => 8,
+                SpriteIndices::Empty => 9,
+                SpriteIndices::DoubleWallCorner => 10,
+                SpriteIndices::DoubleWallStraight => 11,
+                SpriteIndices::HouseWallCorner => 12,
+                SpriteIndices::HouseWallStraight => 13,
+                SpriteIndices::SlowTile => 14,
+                SpriteIndices::OneWayArrow => 15,
+                SpriteIndices::NoGhostsTile => 16,
+                SpriteIndices::Frightened1 => 17,
+                SpriteIndices::Eyes(_) => 18,
+                SpriteIndices::Cherry => 19,
+                SpriteIndices::Strawberry => 20,
+                SpriteIndices::Orange => 21,
+                SpriteIndices::Apple => 22,
+                SpriteIndices::Digit(_) => 23,
+                SpriteIndices::PacmanDeath1 => 24,
+            })
+            .collect::<HashSet<_>>();
+        assert_eq!(variants.len(), 25);
+
+        for direction in [
+            MovementDirection::Up,
+            MovementDirection::Right,
+            MovementDirection::Down,
+            MovementDirection::Left,
+        ] {
+            assert!(SpriteIndices::ALL.contains(&SpriteIndices::Eyes(direction)));
+        }
+        for digit in 0..=9 {
+            assert!(SpriteIndices::ALL.contains(&SpriteIndices::Digit(digit)));
+        }
+
+        let animations = Animation::ALL
+            .iter()
+            .map(|animation| match animation {
+                Animation::Pacman => 0,
+                Animation::Ghost(_) => 1,
+                Animation::Frightened => 2,
+                Animation::FrightenedFlashing => 3,
+                Animation::PacmanDeath => 4,
+            })
+            .collect::<HashSet<_>>();
+        assert_eq!(animations.len(), 5);
+        for ghost in [
+            GhostType::Blinky,
+            GhostType::Inky,
+            GhostType::Pinky,
+            GhostType::Clyde,
+        ] {
+            assert!(Animation::ALL.contains(&Animation::Ghost(ghost)));
+        }
+    }
+
+    #[test]
+    fn test_builtin_atlas() {
+        let atlas = SpriteAtlas::builtin();
+
+        assert_eq!(atlas.index(SpriteIndices::PacmanClosed), 0);
+        assert_eq!(atlas.index(SpriteIndices::HouseWallStraight), 19);
+        assert_eq!(atlas.layout().textures.len(), 56);
+        assert_eq!(
+            atlas.index(SpriteIndices::Eyes(MovementDirection::Left)),
+            31
+        );
+        assert_eq!(atlas.index(SpriteIndices::Digit(7)), 43);
+        assert_eq!(atlas.frames(Animation::PacmanDeath).len(), 8);
+        assert_eq!(atlas.frames(Animation::Ghost(GhostType::Inky)), &[4, 5]);
+    }
+
+    #[test]
+    fn test_next_frame() {
+        let atlas = SpriteAtlas::builtin();
+
+        assert_eq!(atlas.next_frame(Animation::Pacman, 0), 1);
+        assert_eq!(atlas.next_frame(Animation::Pacman, 1), 0);
+        assert_eq!(atlas.next_frame(Animation::Ghost(GhostType::Clyde), 0), 8);
+        assert_eq!(
+            atlas.frames(Animation::FrightenedFlashing),
+            &[24, 26, 25, 27]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_digit_out_of_range() {
+        SpriteIndices::Digit(12).name();
+    }
+
+    #[test]
+    fn test_invalid_atlas() {
+        let missing = ASCII_ATLAS.replace("\"slow_tile\"", "\"slow\"");
+        assert!(matches!(
+            missing.parse::<SpriteAtlas>(),
+            Err(AtlasError::MissingSprite("slow_tile"))
+        ));
+
+        let missing = ASCII_ATLAS.replace("\"pinky\":", "\"pinkie\":");
+        assert!(matches!(
+            missing.parse::<SpriteAtlas>(),
+            Err(AtlasError::MissingAnimation("pinky"))
+        ));
+
+        let outside = ASCII_ATLAS.replace("\"empty\": 14", "\"empty\": 56");
+        assert!(matches!(
+            outside.parse::<SpriteAtlas>(),
+            Err(AtlasError::IndexOutOfRange { index: 56, .. })
+        ));
+
+        let unknown = ASCII_ATLAS.replace(
+            "[\"pacman_closed\", \"pacman_open\"]",
+            "[\"pacman_closed\", \"pacman_wide\"]",
+        );
+        assert!(matches!(
+            unknown.parse::<SpriteAtlas>(),
+            Err(AtlasError::UnknownFrame { sprite, .. }) if sprite == "pacman_wide"
+        ));
+
+        assert!(matches!(
+            "(image: 1)".parse::<SpriteAtlas>(),
+            Err(AtlasError::Parse(_))
+        ));
+    }
+}
@@ -0,0 +1,91 @@
+use std::path::Path;
+
+use bevy::{
+    asset::io::{
+        AssetReader, AssetReaderError, AssetSource, AssetSourceBuilder, AssetSourceId,
+        ErasedAssetReader, PathStream, Reader, VecReader,
+    },
+    prelude::*,
+};
+
+use crate::{ascii::ASCII_SHEET, map::builtin_map};
+
+/// Folder the assets are read from, relative to the asset root.
+pub const ASSETS_PATH: &str = "assets";
+
+/// Plugin making the assets compiled into the binary available, so the game runs without an
+/// assets folder. It has to be added before the `AssetPlugin`.
+pub struct BuiltinAssetsPlugin;
+
+impl Plugin for BuiltinAssetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_asset_source(
+            AssetSourceId::Default,
+            AssetSourceBuilder::platform_default(ASSETS_PATH, None).with_reader(|| {
+                Box::new(BuiltinFallbackReader {
+                    disk: AssetSource::get_default_reader(ASSETS_PATH.to_string())(),
+                })
+            }),
+        );
+    }
+}
+
+/// Get the content of the asset with the given path, which is compiled into the binary.
+fn builtin_asset(path: &Path) -> Option<&'static [u8]> {
+    let path = path.to_str()?;
+    if path == "ascii.png" {
+        return Some(ASCII_SHEET);
+    }
+    builtin_map(path).map(str::as_bytes)
+}
+
+/// Reads assets from the assets folder and falls back to the assets compiled into the binary, if
+/// a file does not exist.
+struct BuiltinFallbackReader {
+    disk: Box<dyn ErasedAssetReader>,
+}
+
+impl AssetReader for BuiltinFallbackReader {
+    async fn read<'a>(&'a self, path: &'a Path) -> Result<Box<Reader<'a>>, AssetReaderError> {
+        match self.disk.read(path).await {
+            Err(AssetReaderError::NotFound(missing)) => match builtin_asset(path) {
+                Some(bytes) => Ok(Box::new(VecReader::new(bytes.to_vec()))),
+                None => Err(AssetReaderError::NotFound(missing)),
+            },
+            result => result,
+        }
+    }
+
+    async fn read_meta<'a>(&'a self, path: &'a Path) -> Result<Box<Reader<'a>>, AssetReaderError> {
+        self.disk.read_meta(path).await
+    }
+
+    async fn read_directory<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> Result<Box<PathStream>, AssetReaderError> {
+        self.disk.read_directory(path).await
+    }
+
+    async fn is_directory<'a>(&'a self, path: &'a Path) -> Result<bool, AssetReaderError> {
+        self.disk.is_directory(path).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::builtin_asset;
+
+    #[test]
+    fn test_builtin_assets() {
+        assert!(builtin_asset(Path::new("ascii.png")).is_some());
+        assert!(builtin_asset(Path::new("lighthouse.pack")).is_some());
+        assert_eq!(
+            builtin_asset(Path::new("map.txt")),
+            Some(include_bytes!("../assets/map.txt").as_slice())
+        );
+        assert_eq!(builtin_asset(Path::new("missing.txt")), None);
+    }
+}
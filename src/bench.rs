@@ -0,0 +1,109 @@
+use std::time::{Duration, Instant};
+
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        texture::{CompressedImageFormats, ImageSampler, ImageType},
+    },
+};
+
+use crate::{
+    ascii::{AsciiSheet, SpriteAtlas, ASCII_SHEET},
+    map::{bake_walls, generate_maze, spawn_tiles, Tile, TileMap, WallRendering},
+};
+
+/// Number of frames measured for every way of rendering the walls.
+const FRAMES: u32 = 100;
+
+/// Seed of the generated maze, so every run benchmarks the same map.
+const SEED: u64 = 0;
+
+/// Spawn the walls of a generated maze in a headless app once per way of rendering them and print
+/// how many entities and sprites are needed and how long a frame takes on average. Since nothing
+/// is drawn, the frame time only covers the work done in the ECS (e.g., propagating transforms).
+pub fn run_benchmark(width: usize, height: usize) -> Result<(), String> {
+    let map = generate_maze(width, height, SEED).map_err(|e| e.to_string())?;
+    println!(
+        "map: {}x{} tiles, {} walls",
+        map.width(),
+        map.height(),
+        map.count(Tile::Wall)
+    );
+
+    for rendering in [WallRendering::Sprites, WallRendering::Baked] {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            TransformPlugin,
+            HierarchyPlugin,
+        ))
+        .init_asset::<Image>()
+        .init_asset::<TextureAtlasLayout>()
+        .insert_resource(rendering)
+        .insert_resource(map.clone())
+        .add_systems(Startup, (load_sheet, spawn_map).chain())
+        .add_systems(Update, bake_walls);
+
+        let start = Instant::now();
+        app.update();
+        let setup = start.elapsed();
+
+        let start = Instant::now();
+        for _ in 0..FRAMES {
+            app.update();
+        }
+        let frame = start.elapsed() / FRAMES;
+
+        let world = app.world_mut();
+        let entities = world.query::<Entity>().iter(world).count();
+        let sprites = world.query::<&Sprite>().iter(world).count();
+
+        println!(
+            "{rendering:?}: {entities} entities, {sprites} sprites, setup {}, frame {}",
+            format_duration(setup),
+            format_duration(frame)
+        );
+    }
+
+    Ok(())
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.3}ms", duration.as_secs_f64() * 1000.0)
+}
+
+/// Decode the built-in sprite sheet directly, since there is no asset server loading it in the
+/// benchmark.
+fn load_sheet(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    let image = Image::from_buffer(
+        ASCII_SHEET,
+        ImageType::Extension("png"),
+        CompressedImageFormats::NONE,
+        true,
+        ImageSampler::Default,
+        RenderAssetUsages::default(),
+    )
+    .expect("the sprite sheet should be a valid png");
+    let atlas = SpriteAtlas::builtin();
+
+    commands.insert_resource(AsciiSheet {
+        image: images.add(image),
+        layout: layouts.add(atlas.layout()),
+        atlas,
+    });
+}
+
+fn spawn_map(
+    mut commands: Commands,
+    map: Res<TileMap>,
+    ascii: Res<AsciiSheet>,
+    rendering: Res<WallRendering>,
+) {
+    spawn_tiles(&mut commands, &map, &ascii, *rendering);
+}
@@ -0,0 +1,222 @@
+use std::{
+    fs, io,
+    path::Path,
+    process::ExitCode,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    bench::run_benchmark,
+    map::{builtin_map, downscale, generate_maze, import_tiled, validate, TileMap},
+};
+
+/// Run the command given on the command line, if there is one. Returns `None`, if the game
+/// should be started instead.
+pub fn run_command(args: &[String]) -> Option<ExitCode> {
+    match args.first().map(String::as_str) {
+        Some("validate") => Some(validate_maps(&args[1..])),
+        Some("generate") => Some(generate(&args[1..])),
+        Some("import") => Some(import(&args[1..])),
+        Some("downscale") => Some(downscale_map(&args[1..])),
+        Some("bench") => Some(bench(&args[1..])),
+        Some("help" | "--help" | "-h") => {
+            print_usage();
+            Some(ExitCode::SUCCESS)
+        }
+        Some(command) => {
+            eprintln!("unknown command '{command}'");
+            print_usage();
+            Some(ExitCode::FAILURE)
+        }
+        None => None,
+    }
+}
+
+fn print_usage() {
+    eprintln!("usage: pacman-bevy [command]");
+    eprintln!();
+    eprintln!("Without a command, the game is started.");
+    eprintln!();
+    eprintln!("commands:");
+    eprintln!(
+        "  validate <map>...                  check map files (or built-in maps) for problems"
+    );
+    eprintln!("  generate <width> <height> [seed]   print a randomly generated maze");
+    eprintln!("  import <file>                      print a map made with Tiled (.tmx or .tmj)");
+    eprintln!("  downscale <map> <width> <height>   print the map shrunk to fit the given size");
+    eprintln!("  bench [width] [height]             compare sprite and baked wall rendering");
+}
+
+/// Read a map file, falling back to the maps compiled into the binary.
+fn read_map(path: &str) -> io::Result<String> {
+    // maps compiled into the binary can be used by their name
+    match fs::read_to_string(path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            builtin_map(path).map(str::to_string).ok_or(e)
+        }
+        result => result,
+    }
+}
+
+/// Validate all given map files and print every problem found.
+fn validate_maps(paths: &[String]) -> ExitCode {
+    if paths.is_empty() {
+        eprintln!("validate: expected at least one map file");
+        return ExitCode::FAILURE;
+    }
+
+    let mut valid = true;
+
+    for path in paths {
+        let map = match read_map(path) {
+            Ok(content) => content.parse::<TileMap>(),
+            Err(e) => {
+                eprintln!("{path}: {e}");
+                valid = false;
+                continue;
+            }
+        };
+
+        match map.map(|map| validate(&map)) {
+            Ok(Ok(())) => println!("{path}: ok"),
+            Ok(Err(problems)) => {
+                valid = false;
+                for problem in problems {
+                    eprintln!("{path}: {problem}");
+                }
+            }
+            Err(e) => {
+                valid = false;
+                eprintln!("{path}:{}:{}: {e}", e.line, e.column);
+            }
+        }
+    }
+
+    if valid {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Generate a maze and print it in the map format.
+fn generate(args: &[String]) -> ExitCode {
+    let parse = |index: usize| args.get(index).map(|arg| arg.parse::<u64>());
+
+    let (Some(Ok(width)), Some(Ok(height))) = (parse(0), parse(1)) else {
+        eprintln!("generate: expected the width and height of the maze");
+        return ExitCode::FAILURE;
+    };
+    let seed = match parse(2) {
+        Some(Ok(seed)) => seed,
+        Some(Err(e)) => {
+            eprintln!("generate: invalid seed: {e}");
+            return ExitCode::FAILURE;
+        }
+        None => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs()),
+    };
+
+    match generate_maze(width as usize, height as usize, seed) {
+        Ok(map) => {
+            print!("{map}");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("generate: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Convert a map made with Tiled and print it in the map format.
+fn import(args: &[String]) -> ExitCode {
+    let [path] = args else {
+        eprintln!("import: expected a single Tiled map");
+        return ExitCode::FAILURE;
+    };
+
+    let map = fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|content| import_tiled(Path::new(path), &content).map_err(|e| e.to_string()));
+
+    match map {
+        Ok(map) => {
+            print!("{map}");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{path}: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Shrink a map to fit the given size, print it in the map format and validate the result.
+fn downscale_map(args: &[String]) -> ExitCode {
+    let [path, width, height] = args else {
+        eprintln!("downscale: expected a map file, the width and the height");
+        return ExitCode::FAILURE;
+    };
+    let (Ok(width), Ok(height)) = (width.parse::<usize>(), height.parse::<usize>()) else {
+        eprintln!("downscale: expected the width and height as numbers");
+        return ExitCode::FAILURE;
+    };
+
+    let map = match read_map(path) {
+        Ok(content) => content.parse::<TileMap>(),
+        Err(e) => {
+            eprintln!("{path}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let map = match map {
+        Ok(map) => map,
+        Err(e) => {
+            eprintln!("{path}:{}:{}: {e}", e.line, e.column);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let small = match downscale(&map, width, height) {
+        Ok(small) => small,
+        Err(e) => {
+            eprintln!("downscale: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    print!("{small}");
+
+    // problems of the original map are kept, so they are reported for the result
+    match validate(&small) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(problems) => {
+            for problem in problems {
+                eprintln!("downscale: {problem}");
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Compare the rendering of walls as sprites and baked into chunks on a generated maze.
+fn bench(args: &[String]) -> ExitCode {
+    let parse = |index: usize, default: usize| {
+        args.get(index)
+            .map_or(Ok(default), |arg| arg.parse::<usize>())
+    };
+
+    let (Ok(width), Ok(height)) = (parse(0, 200), parse(1, 200)) else {
+        eprintln!("bench: expected the width and height of the maze");
+        return ExitCode::FAILURE;
+    };
+
+    match run_benchmark(width, height) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("bench: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
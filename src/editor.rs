@@ -0,0 +1,394 @@
+use std::{fmt, fs};
+
+use bevy::{asset::io::file::FileAssetReader, prelude::*, sprite::Anchor, window::PrimaryWindow};
+
+use crate::{
+    ascii::{AsciiSheet, SpriteIndices},
+    assets::ASSETS_PATH,
+    entities::{GhostType, GridPosition, MovementDirection},
+    map::{
+        respawn_tiles, validate, MapHandle, Marker, SetTile, Tile, TileEntityQuery, TileMap,
+        WallRendering, TILED_EXTENSIONS,
+    },
+    state::GameState,
+};
+
+/// Plugin for editing the loaded map in-game. Press `E` to switch between playing and editing.
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Brush>()
+            .add_systems(
+                OnEnter(GameState::Editor),
+                (spawn_hud, restore_tiles, refresh_markers),
+            )
+            .add_systems(OnExit(GameState::Editor), despawn_editor_entities)
+            .add_systems(
+                Update,
+                toggle_editor
+                    .run_if(in_state(GameState::Playing).or_else(in_state(GameState::Editor))),
+            )
+            .add_systems(
+                Update,
+                (
+                    select_brush,
+                    paint,
+                    save_map,
+                    refresh_markers.run_if(resource_changed::<TileMap>),
+                    update_hud.run_if(resource_changed::<Brush>),
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Editor)),
+            );
+    }
+}
+
+/// What is painted onto the map when clicking a tile.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+enum Brush {
+    Tile(Tile),
+    Marker(Marker),
+}
+
+impl Default for Brush {
+    fn default() -> Self {
+        Self::Tile(Tile::Wall)
+    }
+}
+
+impl fmt::Display for Brush {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Brush::Tile(tile) => write!(f, "{tile:?}"),
+            Brush::Marker(Marker::Ghost(ghost)) => write!(f, "{ghost:?}"),
+            Brush::Marker(marker) => write!(f, "{marker:?}"),
+        }
+    }
+}
+
+/// Keys for selecting each brush.
+const BRUSH_KEYS: [(KeyCode, Brush); 18] = [
+    (KeyCode::Digit1, Brush::Tile(Tile::Empty)),
+    (KeyCode::Digit2, Brush::Tile(Tile::Wall)),
+    (KeyCode::Digit3, Brush::Tile(Tile::Coin)),
+    (KeyCode::KeyT, Brush::Tile(Tile::Tunnel)),
+    (KeyCode::KeyD, Brush::Tile(Tile::Door)),
+    (KeyCode::KeyM, Brush::Tile(Tile::Slow)),
+    (
+        KeyCode::ArrowUp,
+        Brush::Tile(Tile::OneWay(MovementDirection::Up)),
+    ),
+    (
+        KeyCode::ArrowRight,
+        Brush::Tile(Tile::OneWay(MovementDirection::Right)),
+    ),
+    (
+        KeyCode::ArrowDown,
+        Brush::Tile(Tile::OneWay(MovementDirection::Down)),
+    ),
+    (
+        KeyCode::ArrowLeft,
+        Brush::Tile(Tile::OneWay(MovementDirection::Left)),
+    ),
+    (KeyCode::KeyN, Brush::Tile(Tile::NoGhosts)),
+    (KeyCode::Digit4, Brush::Marker(Marker::Pacman)),
+    (
+        KeyCode::Digit5,
+        Brush::Marker(Marker::Ghost(GhostType::Blinky)),
+    ),
+    (
+        KeyCode::Digit6,
+        Brush::Marker(Marker::Ghost(GhostType::Inky)),
+    ),
+    (
+        KeyCode::Digit7,
+        Brush::Marker(Marker::Ghost(GhostType::Pinky)),
+    ),
+    (
+        KeyCode::Digit8,
+        Brush::Marker(Marker::Ghost(GhostType::Clyde)),
+    ),
+    (KeyCode::Digit9, Brush::Marker(Marker::Fruit)),
+    (KeyCode::Digit0, Brush::Marker(Marker::GhostHouse)),
+];
+
+const HELP: &str =
+    "1 empty, 2 wall, 3 coin, t tunnel, d door, m slow, arrows one-way, n no ghosts\n\
+    4 pacman, 5-8 ghosts, 9 fruit, 0 ghost house\n\
+    left click: paint, right click: erase, ctrl+s: save, e: play";
+
+/// Text showing the selected brush and the controls of the editor.
+#[derive(Component)]
+struct EditorHud;
+
+/// Sprite showing a marker, which is not visible as a tile.
+#[derive(Component)]
+struct MarkerPreview;
+
+/// Switch between playing and editing the map.
+fn toggle_editor(
+    input: Res<ButtonInput<KeyCode>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !input.just_pressed(KeyCode::KeyE) {
+        return;
+    }
+
+    match state.get() {
+        GameState::Playing => next_state.set(GameState::Editor),
+        GameState::Editor => next_state.set(GameState::Playing),
+        _ => {}
+    }
+}
+
+fn select_brush(input: Res<ButtonInput<KeyCode>>, mut brush: ResMut<Brush>) {
+    for (key, selected) in BRUSH_KEYS {
+        if input.just_pressed(key) {
+            *brush = selected;
+        }
+    }
+}
+
+/// Paint the selected brush onto the tile below the cursor (left mouse button) or clear the tile
+/// (right mouse button).
+fn paint(
+    mouse: Res<ButtonInput<MouseButton>>,
+    brush: Res<Brush>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    mut map: ResMut<TileMap>,
+    mut set_tile: EventWriter<SetTile>,
+) {
+    let brush = if mouse.pressed(MouseButton::Left) {
+        *brush
+    } else if mouse.pressed(MouseButton::Right) {
+        Brush::Tile(Tile::Empty)
+    } else {
+        return;
+    };
+
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_query.get_single() else {
+        return;
+    };
+    let Some(cursor) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor))
+    else {
+        return;
+    };
+
+    let (x, y) = (cursor.x.floor() as i32, cursor.y.floor() as i32);
+    let Some(current) = map.at(x, y) else {
+        return;
+    };
+    // teleporters have to be placed in pairs, so they are only edited in the map file
+    if let Tile::Teleporter(_) = current {
+        return;
+    }
+    let position = UVec2::new(x as u32, y as u32);
+    let marker = map.spawns().marker_at(position);
+
+    // only touch the markers if they change, since every change spawns all markers again
+    let tile = match brush {
+        Brush::Tile(tile) => {
+            // pacman always needs a spawn, so it can only be moved, but not painted over
+            if position == map.spawns().pacman {
+                return;
+            }
+            if marker.is_some() {
+                map.spawns_mut().remove_at(position);
+            }
+            tile
+        }
+        Brush::Marker(new_marker) => {
+            if marker == Some(Marker::Pacman) {
+                return;
+            }
+            if marker != Some(new_marker) {
+                map.spawns_mut().place(new_marker, position);
+            }
+            Tile::Empty
+        }
+    };
+
+    // the walls around the tile are updated along with it
+    if current != tile {
+        set_tile.send(SetTile { position, tile });
+    }
+}
+
+/// Write the edited map back to its file when pressing `Ctrl+S`.
+fn save_map(
+    input: Res<ButtonInput<KeyCode>>,
+    map: Res<TileMap>,
+    maps: Res<Assets<TileMap>>,
+    handle: Res<MapHandle>,
+) {
+    let ctrl = input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if !ctrl || !input.just_pressed(KeyCode::KeyS) {
+        return;
+    }
+
+    let Some(asset_path) = handle.0.path() else {
+        return;
+    };
+    // writing our format into the file would break it for Tiled, and saving it elsewhere would
+    // not change the map that is played
+    let extension = asset_path.path().extension().and_then(|e| e.to_str());
+    if extension.is_some_and(|extension| TILED_EXTENSIONS.contains(&extension)) {
+        error!(
+            "'{}' was made with Tiled and can not be saved, edit it in Tiled or convert it with \
+            the import command first",
+            asset_path.path().display()
+        );
+        return;
+    }
+    let path = FileAssetReader::get_base_path()
+        .join(ASSETS_PATH)
+        .join(asset_path.path());
+
+    if let Err(problems) = validate(&map) {
+        for problem in problems {
+            warn!("{problem}");
+        }
+    }
+
+    // the header of the file is kept, so the overrides of the level do not end up in it
+    let mut map = map.clone();
+    if let Some(original) = maps.get(&handle.0) {
+        *map.meta_mut() = original.meta().clone();
+    }
+
+    // built-in maps are saved into a new assets folder, which then replaces them
+    if let Some(directory) = path.parent() {
+        if let Err(e) = fs::create_dir_all(directory) {
+            error!("could not create '{}': {e}", directory.display());
+            return;
+        }
+    }
+
+    match fs::write(&path, map.to_string()) {
+        Ok(()) => info!("saved map to '{}'", path.display()),
+        Err(e) => error!("could not save map to '{}': {e}", path.display()),
+    }
+}
+
+/// Spawn all tiles again, so eaten coins are back when editing.
+fn restore_tiles(
+    mut commands: Commands,
+    map: Res<TileMap>,
+    ascii: Res<AsciiSheet>,
+    rendering: Res<WallRendering>,
+    tiles: TileEntityQuery,
+) {
+    respawn_tiles(&mut commands, &map, &ascii, *rendering, &tiles);
+}
+
+/// Spawn the previews of all markers again.
+fn refresh_markers(
+    mut commands: Commands,
+    map: Res<TileMap>,
+    ascii: Res<AsciiSheet>,
+    previews: Query<Entity, With<MarkerPreview>>,
+) {
+    for entity in &previews {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let spawns = map.spawns();
+    let mut markers = vec![(Marker::Pacman, spawns.pacman)];
+    markers.extend(
+        spawns
+            .ghosts
+            .iter()
+            .map(|&(ghost, position)| (Marker::Ghost(ghost), position)),
+    );
+    markers.extend(spawns.fruit.map(|position| (Marker::Fruit, position)));
+    markers.extend(
+        spawns
+            .ghost_house
+            .iter()
+            .map(|&position| (Marker::GhostHouse, position)),
+    );
+
+    for (marker, position) in markers {
+        spawn_marker_preview(&mut commands, &ascii, marker, position);
+    }
+}
+
+fn spawn_marker_preview(
+    commands: &mut Commands,
+    ascii: &Res<AsciiSheet>,
+    marker: Marker,
+    position: UVec2,
+) {
+    let (index, color) = match marker {
+        Marker::Pacman => (SpriteIndices::PacmanClosed, Color::WHITE),
+        Marker::Ghost(ghost) => (SpriteIndices::from(ghost), Color::WHITE),
+        Marker::Fruit => (SpriteIndices::Cherry, Color::WHITE),
+        Marker::GhostHouse => (SpriteIndices::LargeCoin, Color::srgba(1.0, 0.7, 1.0, 0.4)),
+    };
+
+    commands.spawn((
+        MarkerPreview,
+        GridPosition::from(position),
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::splat(1.0)),
+                anchor: Anchor::BottomLeft,
+                color,
+                ..default()
+            },
+            transform: Transform {
+                // x and y are derived from the grid position
+                translation: Vec3::new(0.0, 0.0, 5.0),
+                scale: Vec3::new(1.0, 1.0, 0.0),
+                ..default()
+            },
+            texture: ascii.image.clone(),
+            ..default()
+        },
+        TextureAtlas {
+            layout: ascii.layout.clone(),
+            index: ascii.index(index),
+        },
+    ));
+}
+
+fn spawn_hud(mut commands: Commands, brush: Res<Brush>) {
+    commands.spawn((
+        EditorHud,
+        TextBundle::from_section(
+            hud_text(*brush),
+            TextStyle {
+                font_size: 16.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ),
+    ));
+}
+
+fn update_hud(brush: Res<Brush>, mut hud_query: Query<&mut Text, With<EditorHud>>) {
+    for mut text in hud_query.iter_mut() {
+        text.sections[0].value = hud_text(*brush);
+    }
+}
+
+fn hud_text(brush: Brush) -> String {
+    format!("brush: {brush}\n{HELP}")
+}
+
+type EditorEntityQuery<'world, 'state> =
+    Query<'world, 'state, Entity, Or<(With<EditorHud>, With<MarkerPreview>)>>;
+
+fn despawn_editor_entities(mut commands: Commands, entities: EditorEntityQuery) {
+    for entity in &entities {
+        commands.entity(entity).despawn_recursive();
+    }
+}
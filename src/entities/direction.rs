@@ -3,7 +3,7 @@ use std::ops::{Add, AddAssign};
 use bevy::prelude::*;
 
 /// Enum representing a direction an entity is currently moving in.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum MovementDirection {
     Up,
     Right,
@@ -11,7 +11,21 @@ pub enum MovementDirection {
     Down,
 }
 
+impl MovementDirection {
+    /// Get the direction pointing the other way.
+    pub fn opposite(self) -> Self {
+        match self {
+            MovementDirection::Up => MovementDirection::Down,
+            MovementDirection::Right => MovementDirection::Left,
+            MovementDirection::Left => MovementDirection::Right,
+            MovementDirection::Down => MovementDirection::Up,
+        }
+    }
+}
+
 /// Wrapper around the direction, which allows to dynamically change the direction.
+/// This is the direction an entity wants to go; it actually turns at the centre of the next tile
+/// (see [`Movement`](super::Movement)).
 #[derive(Component, Debug, Default)]
 pub struct DirectionWrapper {
     /// Direction the entity is currently moving in.
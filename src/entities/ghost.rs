@@ -3,30 +3,44 @@ use std::time::Duration;
 use bevy::{prelude::*, sprite::Anchor, time::common_conditions::on_timer};
 
 use crate::{
-    ascii::{AsciiSheet, SpriteIndices},
+    ascii::{Animation, AsciiSheet, SpriteIndices},
+    gameloop::map_speed,
     lighthouse::{LighthouseBundle, LighthouseColor, LighthousePosition},
-    DirectionWrapper,
+    map::{TileMap, Walker},
+    state::GameState,
+    DirectionWrapper, GridPosition, Movement, MovementDirection, TunnelSlowdown,
 };
 
 pub struct GhostPlugin;
 
 const GHOST_TICK_TIME: f64 = 1.0 / 5.0;
 
+/// Ghosts only move at half of their speed inside of tunnels.
+const GHOST_TUNNEL_SLOWDOWN: f32 = 0.5;
+
 impl Plugin for GhostPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_ghosts)
+        app.add_systems(OnEnter(GameState::Playing), spawn_ghosts)
+            .add_systems(OnExit(GameState::Playing), despawn_ghosts)
             .add_systems(
                 Update,
-                animate_ghost_sprite.run_if(on_timer(Duration::from_secs_f64(GHOST_TICK_TIME))),
-            )
-            .add_systems(Update, update_lighthouse_position);
+                (
+                    animate_ghost_sprite.run_if(on_timer(Duration::from_secs_f64(GHOST_TICK_TIME))),
+                    turn_ghost_eyes,
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
     }
 }
 
 #[derive(Component)]
 pub struct Ghost;
 
-#[derive(Clone, Copy, Component)]
+/// Component for the eyes of a ghost, which are drawn on top of it and look where it is going.
+#[derive(Component)]
+pub struct GhostEyes;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
 pub enum GhostType {
     Blinky,
     Inky,
@@ -56,19 +70,28 @@ impl From<GhostType> for LighthouseColor {
     }
 }
 
-fn spawn_ghosts(mut commands: Commands, ascii: Res<AsciiSheet>) {
-    spawn_specific_ghost(&mut commands, &ascii, GhostType::Blinky, 5, 6);
-    spawn_specific_ghost(&mut commands, &ascii, GhostType::Inky, 5, 8);
-    spawn_specific_ghost(&mut commands, &ascii, GhostType::Pinky, 8, 8);
-    spawn_specific_ghost(&mut commands, &ascii, GhostType::Clyde, 8, 6);
+/// Spawn every ghost placed on the map at its spawn point
+fn spawn_ghosts(mut commands: Commands, ascii: Res<AsciiSheet>, map: Res<TileMap>) {
+    let speed = map_speed(&map);
+
+    for &(ghost, position) in &map.spawns().ghosts {
+        spawn_specific_ghost(&mut commands, &ascii, ghost, position.into(), speed);
+    }
+}
+
+/// Remove all ghosts, so they start at their spawn points again when playing the next time
+fn despawn_ghosts(mut commands: Commands, query: Query<Entity, With<Ghost>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
 }
 
 fn spawn_specific_ghost(
     commands: &mut Commands,
     ascii: &Res<AsciiSheet>,
     ghost: GhostType,
-    x: usize,
-    y: usize,
+    position: GridPosition,
+    speed: f32,
 ) {
     let layout = ascii.layout.clone();
     let texture = ascii.image.clone();
@@ -80,18 +103,30 @@ fn spawn_specific_ghost(
     };
 
     let atlas = TextureAtlas {
+        layout: layout.clone(),
+        index: ascii.index(SpriteIndices::from(ghost)),
+    };
+
+    let eyes_atlas = TextureAtlas {
         layout,
-        index: SpriteIndices::from(ghost).into(),
+        index: ascii.index(SpriteIndices::Eyes(MovementDirection::Right)),
     };
 
     commands
         .spawn(Ghost)
         .insert(ghost)
+        .insert(position)
+        .insert((
+            Movement::new(speed),
+            TunnelSlowdown(GHOST_TUNNEL_SLOWDOWN),
+            Walker::Ghost,
+        ))
         .insert((
             SpriteBundle {
                 transform: Transform {
-                    translation: Vec3::new(x as f32, y as f32, 10.0),
-                    scale: Vec3::new(1.0, 1.0, 0.0),
+                    // x and y are derived from the grid position
+                    // the z scale is kept, so the eyes are drawn in front of the ghost
+                    translation: Vec3::new(0.0, 0.0, 10.0),
                     ..default()
                 },
                 sprite: sprite.clone(),
@@ -101,28 +136,51 @@ fn spawn_specific_ghost(
             atlas,
         ))
         .insert(LighthouseBundle {
-            position: LighthousePosition { x, y, z: 2 },
+            position: LighthousePosition { x: 0, y: 0, z: 2 },
             color: ghost.into(),
         })
-        .insert(DirectionWrapper::default());
+        .insert(DirectionWrapper::default())
+        .with_children(|parent| {
+            parent.spawn((
+                GhostEyes,
+                SpriteBundle {
+                    transform: Transform::from_xyz(0.0, 0.0, 0.1),
+                    sprite,
+                    texture,
+                    ..default()
+                },
+                eyes_atlas,
+            ));
+        });
 }
 
-fn update_lighthouse_position(
-    mut query: Query<(&Transform, &mut LighthousePosition), With<Ghost>>,
+fn animate_ghost_sprite(
+    ascii: Res<AsciiSheet>,
+    mut ghost_query: Query<(&GhostType, &mut TextureAtlas), With<Ghost>>,
 ) {
-    for (Transform { translation, .. }, mut position) in query.iter_mut() {
-        position.x = translation.x.max(0.0) as usize;
-        position.y = translation.y.max(0.0) as usize;
-        position.z = translation.z.max(0.0) as usize;
+    for (ghost, mut sprite) in ghost_query.iter_mut() {
+        sprite.index = ascii
+            .atlas
+            .next_frame(Animation::Ghost(*ghost), sprite.index);
     }
 }
 
-fn animate_ghost_sprite(mut ghost_query: Query<&mut TextureAtlas, With<Ghost>>) {
-    for mut sprite in ghost_query.iter_mut() {
-        if sprite.index % 2 == 0 {
-            sprite.index += 1;
-        } else {
-            sprite.index -= 1;
+/// Let the eyes of every ghost look in the direction it is going.
+fn turn_ghost_eyes(
+    ascii: Res<AsciiSheet>,
+    ghost_query: Query<(&DirectionWrapper, &Children), With<Ghost>>,
+    mut eyes_query: Query<&mut TextureAtlas, With<GhostEyes>>,
+) {
+    for (direction, children) in ghost_query.iter() {
+        let Some(direction) = direction.direction else {
+            continue;
+        };
+        let mut eyes = eyes_query.iter_many_mut(children);
+        while let Some(mut sprite) = eyes.fetch_next() {
+            let index = ascii.index(SpriteIndices::Eyes(direction));
+            if sprite.index != index {
+                sprite.index = index;
+            }
         }
     }
 }
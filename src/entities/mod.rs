@@ -1,10 +1,12 @@
 mod direction;
 mod ghost;
 mod player;
+mod position;
 
 pub use direction::*;
 pub use ghost::*;
 pub use player::*;
+pub use position::*;
 
 use bevy::prelude::*;
 
@@ -12,6 +14,6 @@ pub struct EntityPlugin;
 
 impl Plugin for EntityPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((PlayerPlugin, GhostPlugin));
+        app.add_plugins((PlayerPlugin, GhostPlugin, PositionPlugin));
     }
 }
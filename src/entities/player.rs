@@ -6,9 +6,12 @@ use std::{
 use bevy::{prelude::*, sprite::Anchor, time::common_conditions::on_timer};
 
 use crate::{
-    ascii::{AsciiSheet, SpriteIndices},
+    ascii::{Animation, AsciiSheet, SpriteIndices},
+    gameloop::map_speed,
     lighthouse::{LighthouseBundle, LighthouseColor, LighthousePosition},
-    DirectionWrapper, MovementDirection,
+    map::{TileMap, Walker},
+    state::GameState,
+    DirectionWrapper, GridPosition, Movement, MovementDirection,
 };
 
 pub struct PlayerPlugin;
@@ -17,13 +20,16 @@ const TICK_TIME: f64 = 1.0 / 4.0;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_player)
-            .add_systems(Update, (check_for_input, rotate_pacman_head).chain())
+        app.add_systems(OnEnter(GameState::Playing), spawn_player)
+            .add_systems(OnExit(GameState::Playing), despawn_player)
             .add_systems(
                 Update,
-                change_pacman_mouth.run_if(on_timer(Duration::from_secs_f64(TICK_TIME))),
-            )
-            .add_systems(Update, update_lighthouse_position);
+                (
+                    (check_for_input, rotate_pacman_head).chain(),
+                    change_pacman_mouth.run_if(on_timer(Duration::from_secs_f64(TICK_TIME))),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
     }
 }
 
@@ -31,8 +37,17 @@ impl Plugin for PlayerPlugin {
 #[derive(Component)]
 pub struct Pacman;
 
-/// Spawn a new player entity and all its components
-fn spawn_player(mut commands: Commands, ascii: Res<AsciiSheet>) {
+/// Remove pacman, so it starts at its spawn point again when playing the next time
+fn despawn_player(mut commands: Commands, query: Query<Entity, With<Pacman>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Spawn a new player entity and all its components at the spawn point of the map
+fn spawn_player(mut commands: Commands, ascii: Res<AsciiSheet>, map: Res<TileMap>) {
+    let movement = Movement::new(map_speed(&map));
+    let position = GridPosition::from(map.spawns().pacman);
     let layout = ascii.layout.clone();
     let texture = ascii.image.clone();
 
@@ -44,15 +59,18 @@ fn spawn_player(mut commands: Commands, ascii: Res<AsciiSheet>) {
 
     let atlas = TextureAtlas {
         layout,
-        index: SpriteIndices::PacmanClosed.into(),
+        index: ascii.index(SpriteIndices::PacmanClosed),
     };
 
     commands
         .spawn(Pacman)
+        .insert(position)
+        .insert((movement, Walker::Pacman))
         .insert((
             SpriteBundle {
                 transform: Transform {
-                    translation: Vec3::new(1.0, 1.0, 10.0),
+                    // x and y are derived from the grid position
+                    translation: Vec3::new(0.0, 0.0, 10.0),
                     scale: Vec3::new(1.0, 1.0, 0.0),
                     ..default()
                 },
@@ -64,20 +82,11 @@ fn spawn_player(mut commands: Commands, ascii: Res<AsciiSheet>) {
         ))
         .insert(DirectionWrapper::default())
         .insert(LighthouseBundle {
-            position: LighthousePosition { x: 1, y: 1, z: 10 },
+            position: LighthousePosition { x: 0, y: 0, z: 10 },
             color: LighthouseColor::Inline(255, 255, 0),
         });
 }
 
-fn update_lighthouse_position(
-    mut query: Query<(&Transform, &mut LighthousePosition), With<Pacman>>,
-) {
-    let (Transform { translation, .. }, mut position) = query.single_mut();
-    position.x = translation.x.max(0.0) as usize;
-    position.y = translation.y.max(0.0) as usize;
-    position.z = translation.z.max(0.0) as usize;
-}
-
 /// Check, if there are any important keys pressed by the user.
 fn check_for_input(
     keyboard_input: Res<ButtonInput<KeyCode>>,
@@ -123,14 +132,10 @@ fn rotate_pacman_head(
     sprite.anchor = anchor;
 }
 
-fn change_pacman_mouth(mut pacman_query: Query<&mut TextureAtlas, With<Pacman>>) {
+fn change_pacman_mouth(
+    ascii: Res<AsciiSheet>,
+    mut pacman_query: Query<&mut TextureAtlas, With<Pacman>>,
+) {
     let mut sprite = pacman_query.single_mut();
-    let open_mouth: usize = SpriteIndices::PacmanOpen.into();
-    let closed_mouth: usize = SpriteIndices::PacmanClosed.into();
-
-    if sprite.index == open_mouth {
-        sprite.index = closed_mouth;
-    } else {
-        sprite.index = open_mouth;
-    }
+    sprite.index = ascii.atlas.next_frame(Animation::Pacman, sprite.index);
 }
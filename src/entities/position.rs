@@ -0,0 +1,134 @@
+use std::ops::Add;
+
+use bevy::{prelude::*, transform::TransformSystem};
+
+use crate::lighthouse::LighthousePosition;
+
+use super::MovementDirection;
+
+/// Plugin keeping the `Transform` and `LighthousePosition` of every entity in sync with its
+/// `GridPosition`.
+pub struct PositionPlugin;
+
+impl Plugin for PositionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            (sync_positions, interpolate_positions)
+                .chain()
+                .before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// Position of an entity in tile coordinates, i.e., `(0, 0)` is the bottom left tile of the map.
+/// Gameplay only reads and writes this position; everything else is derived from it.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GridPosition {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl GridPosition {
+    pub fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+}
+
+impl From<UVec2> for GridPosition {
+    fn from(value: UVec2) -> Self {
+        Self::new(value.x as i32, value.y as i32)
+    }
+}
+
+impl Add<MovementDirection> for GridPosition {
+    type Output = GridPosition;
+
+    fn add(self, rhs: MovementDirection) -> Self::Output {
+        let (dx, dy) = match rhs {
+            MovementDirection::Up => (0, 1),
+            MovementDirection::Right => (1, 0),
+            MovementDirection::Down => (0, -1),
+            MovementDirection::Left => (-1, 0),
+        };
+        Self::new(self.x + dx, self.y + dy)
+    }
+}
+
+/// Continuous movement of an entity between the centres of two tiles. The grid position is the
+/// tile the entity leaves, until it fully arrived at the next one. An entity turning around on
+/// the way keeps its grid position and moves back to its centre.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Default)]
+pub struct Movement {
+    /// Tiles per second.
+    pub speed: f32,
+    /// Direction the entity is moving in, `None` if it stands still.
+    pub heading: Option<MovementDirection>,
+    /// How far (between `0` and `1`) the entity moved towards the next tile. It is negative (down
+    /// to `-1`), while the entity moves back towards the centre of its tile after turning around.
+    pub progress: f32,
+}
+
+impl Movement {
+    pub fn new(speed: f32) -> Self {
+        Self { speed, ..default() }
+    }
+}
+
+/// Factor applied to the speed of an entity while it is inside of a tunnel.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct TunnelSlowdown(pub f32);
+
+type PositionQuery<'world, 'state, 'a> = Query<
+    'world,
+    'state,
+    (
+        &'a GridPosition,
+        Option<&'a mut Transform>,
+        Option<&'a mut LighthousePosition>,
+    ),
+    Changed<GridPosition>,
+>;
+
+/// Move the sprite and the pixel on the lighthouse of every entity whose grid position changed.
+/// The depth (z) of both is left untouched.
+fn sync_positions(mut query: PositionQuery) {
+    for (position, transform, lighthouse_position) in query.iter_mut() {
+        if let Some(mut transform) = transform {
+            transform.translation.x = position.x as f32;
+            transform.translation.y = position.y as f32;
+        }
+        if let Some(mut lighthouse_position) = lighthouse_position {
+            lighthouse_position.x = position.x.max(0) as usize;
+            lighthouse_position.y = position.y.max(0) as usize;
+        }
+    }
+}
+
+/// Render moving entities between the tile they leave and the tile they move to. This is only
+/// visual, the lighthouse keeps showing the grid position.
+fn interpolate_positions(mut query: Query<(&GridPosition, &Movement, &mut Transform)>) {
+    for (position, movement, mut transform) in query.iter_mut() {
+        let offset = movement.heading.map_or(Vec3::ZERO, |heading| {
+            Vec3::from(heading) * movement.progress
+        });
+
+        transform.translation.x = position.x as f32 + offset.x;
+        transform.translation.y = position.y as f32 + offset.y;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entities::{GridPosition, MovementDirection};
+
+    #[test]
+    fn test_add_direction() {
+        let position = GridPosition::new(3, 3);
+
+        assert_eq!(position + MovementDirection::Up, GridPosition::new(3, 4));
+        assert_eq!(position + MovementDirection::Right, GridPosition::new(4, 3));
+        assert_eq!(position + MovementDirection::Down, GridPosition::new(3, 2));
+        assert_eq!(position + MovementDirection::Left, GridPosition::new(2, 3));
+    }
+}
@@ -1,14 +1,18 @@
-use std::time::Duration;
-
-use bevy::{prelude::*, time::common_conditions::on_timer};
+use bevy::prelude::*;
 
 use crate::{
-    entities::{DirectionWrapper, Pacman},
-    map::{Coin, WallTile},
-    view::{COLUMNS, ROWS},
+    entities::{
+        DirectionWrapper, GridPosition, Movement, MovementDirection, Pacman, TunnelSlowdown,
+    },
+    map::{Grid, Tile, TileMap, Walker},
+    state::GameState,
 };
 
-const TICK_TIME: f64 = 1.0 / 2.0;
+/// Tiles pacman moves per second, if the map does not specify its own tick speed.
+const DEFAULT_TICK_SPEED: f64 = 2.0;
+
+/// Factor applied to the speed of every entity on a slow tile.
+const SLOW_TILE_SLOWDOWN: f32 = 0.5;
 
 /// Plugin for managing the game loop of the game
 pub struct GameLoop;
@@ -18,76 +22,425 @@ impl Plugin for GameLoop {
         app.add_systems(
             Update,
             (
-                move_player.run_if(on_timer(Duration::from_millis((TICK_TIME * 1000.0) as u64))),
+                update_speed.run_if(resource_exists_and_changed::<TileMap>),
+                move_entities,
                 eat_coin,
             )
-                .chain(),
+                .chain()
+                .run_if(in_state(GameState::Playing)),
         );
     }
 }
 
-/// Move the player according to its current position
-fn move_player(
-    mut transform_direction_query: Query<(&mut Transform, &DirectionWrapper), With<Pacman>>,
-    wall_query: Query<&Transform, (With<WallTile>, Without<Pacman>)>,
+/// Speed (in tiles per second) of entities on the given map.
+pub fn map_speed(map: &TileMap) -> f32 {
+    map.meta().tick_speed.unwrap_or(DEFAULT_TICK_SPEED) as f32
+}
+
+/// Update the speed of all moving entities according to the tick speed of the map.
+fn update_speed(map: Res<TileMap>, mut query: Query<&mut Movement>) {
+    let speed = map_speed(&map);
+
+    for mut movement in query.iter_mut() {
+        movement.speed = speed;
+    }
+}
+
+/// Move all entities continuously in the direction they want to go.
+fn move_entities(
+    time: Res<Time>,
+    grid: Res<Grid>,
+    mut query: Query<(
+        &mut GridPosition,
+        &mut Movement,
+        &DirectionWrapper,
+        &Walker,
+        Option<&TunnelSlowdown>,
+    )>,
 ) {
-    let (mut transform, direction_wrapper) = transform_direction_query.single_mut();
-    let direction = direction_wrapper.direction;
-    let Some(direction) = direction else {
-        return;
-    };
-    // calculate the new position according to the current direction
-    let mut new_position = transform.translation + direction;
-
-    // we convert x and y to integers so we avoid floating point errors
-    let x = new_position.x as i32;
-    let y = new_position.y as i32;
-
-    // check for collisions with each wall
-    for wall in &wall_query {
-        let wall_position = wall.translation;
-        if wall_position.x.floor() as i32 == x && wall_position.y.floor() as i32 == y {
-            return;
+    for (mut position, mut movement, direction_wrapper, walker, slowdown) in query.iter_mut() {
+        let mut speed = movement.speed;
+        let tile = grid.tile(position.x, position.y);
+        if let Some(TunnelSlowdown(factor)) = slowdown {
+            if tile == Some(Tile::Tunnel) {
+                speed *= factor;
+            }
+        }
+        if tile == Some(Tile::Slow) {
+            speed *= SLOW_TILE_SLOWDOWN;
+        }
+
+        let distance = speed * time.delta_seconds();
+        let (new_position, new_movement) = advance(
+            *position,
+            *movement,
+            direction_wrapper.direction,
+            *walker,
+            &grid,
+            distance,
+        );
+
+        // only trigger change detection, if something actually changed
+        if new_position != *position {
+            *position = new_position;
+        }
+        if new_movement != *movement {
+            *movement = new_movement;
+        }
+    }
+}
+
+/// Move an entity the given distance (in tiles) from its position. The entity only turns into
+/// the desired direction (or stops, if there is none) at the centre of a tile, unless it turns
+/// around. It stops in front of walls (and doors, unless it is a ghost, or tiles without ghosts,
+/// if it is one), never moves against the arrow of a one-way tile, wraps around the edges of the
+/// map in tunnels and is teleported when arriving at a teleporter.
+fn advance(
+    mut position: GridPosition,
+    mut movement: Movement,
+    desired: Option<MovementDirection>,
+    walker: Walker,
+    grid: &Grid,
+    mut distance: f32,
+) -> (GridPosition, Movement) {
+    let walkable =
+        |position: GridPosition, direction| next_tile(position, direction, walker, grid).is_some();
+
+    // turning around is possible at any time, unless it is against a one-way tile. The grid
+    // position is kept, the entity now moves back towards its centre (with negative progress) or
+    // away from it again.
+    if let (Some(heading), Some(desired)) = (movement.heading, desired) {
+        let towards = if movement.progress > 0.0 {
+            heading
+        } else {
+            desired
+        };
+        let other = next_tile(position, towards, walker, grid);
+        let allowed = (movement.progress > 0.0 || other.is_some())
+            && [Some(position), other]
+                .into_iter()
+                .flatten()
+                .all(|position| {
+                    grid.tile(position.x, position.y)
+                        .is_none_or(|tile| tile.allows(desired))
+                });
+
+        if movement.progress != 0.0 && desired == heading.opposite() && allowed {
+            movement.heading = Some(desired);
+            movement.progress = -movement.progress;
         }
     }
 
-    // check for "going out of bounds"
-    if x < 0 {
-        new_position.x = (COLUMNS - 1) as f32;
-    } else if x >= COLUMNS as i32 {
-        new_position.x = 0.0;
+    loop {
+        if movement.progress == 0.0 {
+            // we are at the centre of a tile, so we may turn
+            movement.heading = match (desired, movement.heading) {
+                (Some(desired), _) if walkable(position, desired) => Some(desired),
+                (Some(_), Some(heading)) if walkable(position, heading) => Some(heading),
+                _ => None,
+            };
+        }
+
+        let Some(heading) = movement.heading else {
+            break;
+        };
+        if distance <= 0.0 {
+            break;
+        }
+
+        // when moving back, the entity stops at the centre of its tile, so it may turn there
+        let remaining = if movement.progress < 0.0 {
+            -movement.progress
+        } else {
+            1.0 - movement.progress
+        };
+        let step = distance.min(remaining);
+        movement.progress += step;
+        distance -= step;
+
+        if movement.progress >= 1.0 {
+            position = next_tile(position, heading, walker, grid).unwrap_or(position);
+            if let Some(target) = grid.teleport_target(position.x, position.y) {
+                position = target.into();
+            }
+            movement.progress = 0.0;
+        }
     }
 
-    if y < 0 {
-        new_position.y = (ROWS - 1) as f32;
-    } else if y >= ROWS as i32 {
-        new_position.y = 0.0;
+    (position, movement)
+}
+
+/// Get the tile next to the position in the given direction, if an entity may move there.
+/// Leaving the map is only possible through tunnels, which lead to the opposite edge.
+fn next_tile(
+    position: GridPosition,
+    direction: MovementDirection,
+    walker: Walker,
+    grid: &Grid,
+) -> Option<GridPosition> {
+    let mut next = position + direction;
+
+    if grid.tile(next.x, next.y).is_none() {
+        if grid.tile(position.x, position.y) != Some(Tile::Tunnel) {
+            return None;
+        }
+        next = GridPosition::new(
+            next.x.rem_euclid(grid.width() as i32),
+            next.y.rem_euclid(grid.height() as i32),
+        );
     }
 
-    transform.translation = new_position;
+    grid.can_move(position, next, direction, walker)
+        .then_some(next)
 }
 
-type PacmanQuery<'world, 'state, 'a> =
-    Query<'world, 'state, &'a Transform, (With<Pacman>, Without<Coin>, Without<WallTile>)>;
-
-type CoinQuery<'world, 'state, 'a> = Query<
-    'world,
-    'state,
-    (&'a Transform, Entity),
-    (With<Coin>, Without<Pacman>, Without<WallTile>),
->;
-
-/// Eat the coin at the current location of pacman
-fn eat_coin(mut commands: Commands, pacman_query: PacmanQuery, coins: CoinQuery) {
-    // convert coordinate to u32 to avoid floating point errors
-    let transform = pacman_query.single();
-    let x = transform.translation.x as u32;
-    let y = transform.translation.y as u32;
-
-    // check if there is a coin at the current position
-    for (position, coin) in &coins {
-        if x == position.translation.x as u32 && y == position.translation.y as u32 {
-            commands.entity(coin).despawn();
+/// Eat the coin at the current location of pacman. The level is cleared, once all coins are
+/// eaten.
+fn eat_coin(
+    mut commands: Commands,
+    pacman_query: Query<&GridPosition, With<Pacman>>,
+    mut grid: ResMut<Grid>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let position = pacman_query.single();
+
+    if let Some(coin) = grid.take_coin(position.x, position.y) {
+        commands.entity(coin).despawn();
+
+        if grid.coins_left() == 0 {
+            next_state.set(GameState::LevelCleared);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::{ecs::system::RunSystemOnce, prelude::*};
+
+    use crate::{
+        entities::{GridPosition, Movement, MovementDirection, Pacman},
+        map::{Grid, Tile, TileMap, Walker},
+        state::GameState,
+    };
+
+    use super::{advance, eat_coin};
+
+    fn grid(map: &str) -> Grid {
+        Grid::from(&map.parse::<TileMap>().unwrap())
+    }
+
+    #[test]
+    fn test_advance_within_tile() {
+        let grid = grid("#####\n#@..#\n#####");
+        let (position, movement) = advance(
+            GridPosition::new(1, 1),
+            Movement::new(2.0),
+            Some(MovementDirection::Right),
+            Walker::Pacman,
+            &grid,
+            0.5,
+        );
+
+        assert_eq!(position, GridPosition::new(1, 1));
+        assert_eq!(movement.heading, Some(MovementDirection::Right));
+        assert_eq!(movement.progress, 0.5);
+    }
+
+    #[test]
+    fn test_advance_stops_at_wall() {
+        let grid = grid("#####\n#@..#\n#####");
+        let (position, movement) = advance(
+            GridPosition::new(1, 1),
+            Movement::new(2.0),
+            Some(MovementDirection::Right),
+            Walker::Pacman,
+            &grid,
+            5.0,
+        );
+
+        assert_eq!(position, GridPosition::new(3, 1));
+        assert_eq!(movement.heading, None);
+        assert_eq!(movement.progress, 0.0);
+    }
+
+    #[test]
+    fn test_advance_turns_only_at_centre() {
+        let grid = grid("#####\n#...#\n#@..#\n#####");
+        let moving = Movement {
+            heading: Some(MovementDirection::Right),
+            progress: 0.5,
+            ..Movement::new(2.0)
+        };
+
+        // the turn is remembered until the next centre is reached
+        let (position, movement) = advance(
+            GridPosition::new(1, 1),
+            moving,
+            Some(MovementDirection::Up),
+            Walker::Pacman,
+            &grid,
+            0.75,
+        );
+        assert_eq!(position, GridPosition::new(2, 1));
+        assert_eq!(movement.heading, Some(MovementDirection::Up));
+        assert_eq!(movement.progress, 0.25);
+
+        // turning around works everywhere
+        let (position, movement) = advance(
+            GridPosition::new(1, 1),
+            moving,
+            Some(MovementDirection::Left),
+            Walker::Pacman,
+            &grid,
+            0.25,
+        );
+        assert_eq!(position, GridPosition::new(1, 1));
+        assert_eq!(movement.heading, Some(MovementDirection::Left));
+        assert_eq!(movement.progress, -0.25);
+
+        // back at the centre, the entity may turn again
+        let (position, movement) = advance(
+            GridPosition::new(1, 1),
+            moving,
+            Some(MovementDirection::Left),
+            Walker::Pacman,
+            &grid,
+            0.75,
+        );
+        assert_eq!(position, GridPosition::new(1, 1));
+        assert_eq!(movement.heading, None);
+        assert_eq!(movement.progress, 0.0);
+    }
+
+    #[test]
+    fn test_advance_wraps_around_in_tunnels() {
+        let grid = grid("#####\n=@..=\n.....\n#####");
+        let (position, _) = advance(
+            GridPosition::new(0, 2),
+            Movement::new(2.0),
+            Some(MovementDirection::Left),
+            Walker::Pacman,
+            &grid,
+            1.0,
+        );
+        assert_eq!(position, GridPosition::new(4, 2));
+
+        // without a tunnel, the border can not be crossed
+        let (position, movement) = advance(
+            GridPosition::new(0, 1),
+            Movement::new(2.0),
+            Some(MovementDirection::Left),
+            Walker::Pacman,
+            &grid,
+            1.0,
+        );
+        assert_eq!(position, GridPosition::new(0, 1));
+        assert_eq!(movement.heading, None);
+    }
+
+    #[test]
+    fn test_advance_teleports() {
+        let grid = grid("#######\n#@1#1.#\n#######");
+        let (position, movement) = advance(
+            GridPosition::new(1, 1),
+            Movement::new(2.0),
+            Some(MovementDirection::Right),
+            Walker::Pacman,
+            &grid,
+            1.5,
+        );
+
+        assert_eq!(position, GridPosition::new(4, 1));
+        assert_eq!(movement.heading, Some(MovementDirection::Right));
+        assert_eq!(movement.progress, 0.5);
+    }
+
+    #[test]
+    fn test_advance_through_doors() {
+        let grid = grid("#####\n#@-.#\n#####");
+        let pacman = advance(
+            GridPosition::new(1, 1),
+            Movement::new(2.0),
+            Some(MovementDirection::Right),
+            Walker::Pacman,
+            &grid,
+            2.0,
+        );
+        let ghost = advance(
+            GridPosition::new(1, 1),
+            Movement::new(2.0),
+            Some(MovementDirection::Right),
+            Walker::Ghost,
+            &grid,
+            2.0,
+        );
+
+        assert_eq!(pacman.0, GridPosition::new(1, 1));
+        assert_eq!(ghost.0, GridPosition::new(3, 1));
+    }
+
+    #[test]
+    fn test_advance_on_one_way_tiles() {
+        let grid = grid("######\n#@.>.#\n######");
+        let against = advance(
+            GridPosition::new(4, 1),
+            Movement::new(2.0),
+            Some(MovementDirection::Left),
+            Walker::Pacman,
+            &grid,
+            2.0,
+        );
+        assert_eq!(against.0, GridPosition::new(4, 1));
+        assert_eq!(against.1.heading, None);
+
+        // entering along the arrow works, but turning around on the way does not
+        let moving = Movement {
+            heading: Some(MovementDirection::Right),
+            progress: 0.5,
+            ..Movement::new(2.0)
+        };
+        let (position, movement) = advance(
+            GridPosition::new(2, 1),
+            moving,
+            Some(MovementDirection::Left),
+            Walker::Pacman,
+            &grid,
+            0.25,
+        );
+        assert_eq!(position, GridPosition::new(2, 1));
+        assert_eq!(movement.heading, Some(MovementDirection::Right));
+        assert_eq!(movement.progress, 0.75);
+    }
+
+    #[test]
+    fn test_turning_around_keeps_position() {
+        let grid = grid("#####\n#@..#\n#####");
+        let (position, movement) = advance(
+            GridPosition::new(1, 1),
+            Movement::new(2.0),
+            Some(MovementDirection::Right),
+            Walker::Pacman,
+            &grid,
+            0.1,
+        );
+        let (position, movement) = advance(
+            position,
+            movement,
+            Some(MovementDirection::Left),
+            Walker::Pacman,
+            &grid,
+            0.05,
+        );
+        assert_eq!(position, GridPosition::new(1, 1));
+        assert_eq!(movement.heading, Some(MovementDirection::Left));
+
+        // pacman never reached the coin next to him, so it is not eaten
+        let mut world = World::new();
+        world.insert_resource(grid);
+        world.init_resource::<NextState<GameState>>();
+        world.spawn((Pacman, position));
+        world.run_system_once(eat_coin);
+
+        assert_eq!(world.resource::<Grid>().tile(2, 1), Some(Tile::Coin));
+    }
+}
@@ -0,0 +1,206 @@
+use bevy::prelude::*;
+
+use crate::{
+    map::{AfterLastLevel, CurrentLevel, LevelStarter},
+    state::GameState,
+};
+
+/// Plugin for moving through the levels of the pack and showing the victory screen after the
+/// last one.
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::LevelCleared), next_level)
+            .add_systems(OnEnter(GameState::Victory), spawn_victory_screen)
+            .add_systems(OnExit(GameState::Victory), despawn_victory_screen)
+            .add_systems(Update, restart_pack.run_if(in_state(GameState::Victory)));
+    }
+}
+
+/// Text shown after the last level of the pack is cleared.
+#[derive(Component)]
+struct VictoryScreen;
+
+/// Start the level after the cleared one. After the last level, the pack either starts again or
+/// the game is won.
+fn next_level(
+    mut levels: LevelStarter,
+    mut current: ResMut<CurrentLevel>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(pack) = levels.pack() else {
+        return;
+    };
+
+    let mut next = current.0 + 1;
+    if next >= pack.levels.len() {
+        if pack.after_last == AfterLastLevel::Victory {
+            next_state.set(GameState::Victory);
+            return;
+        }
+        next = 0;
+    }
+
+    if !levels.start(next) {
+        error!("level {} is not loaded", next + 1);
+        next_state.set(GameState::MapError);
+        return;
+    }
+
+    info!("starting level {}", next + 1);
+    current.0 = next;
+    next_state.set(GameState::Playing);
+}
+
+fn spawn_victory_screen(mut commands: Commands, levels: LevelStarter) {
+    let name = levels
+        .pack()
+        .and_then(|pack| pack.name.clone())
+        .unwrap_or_else(|| "all levels".to_string());
+
+    commands.spawn((
+        VictoryScreen,
+        TextBundle::from_section(
+            format!("You cleared {name}!\nPress enter to play again."),
+            TextStyle {
+                font_size: 20.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ),
+    ));
+}
+
+fn despawn_victory_screen(mut commands: Commands, query: Query<Entity, With<VictoryScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Start the first level of the pack again, when enter is pressed.
+fn restart_pack(
+    input: Res<ButtonInput<KeyCode>>,
+    mut levels: LevelStarter,
+    mut current: ResMut<CurrentLevel>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !input.just_pressed(KeyCode::Enter) || !levels.start(0) {
+        return;
+    }
+
+    current.0 = 0;
+    next_state.set(GameState::Playing);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{ecs::system::RunSystemOnce, prelude::*, state::app::StatesPlugin};
+
+    use crate::{
+        ascii::{AsciiSheet, SpriteAtlas},
+        entities::{GridPosition, Pacman},
+        gameloop::GameLoop,
+        map::{
+            AfterLastLevel, CurrentLevel, Level, LevelPack, LevelPackHandle, LevelStarter, TileMap,
+            WallRendering,
+        },
+        state::GameState,
+    };
+
+    use super::LevelPlugin;
+
+    /// A headless app playing a pack of two levels, each with a single coin at `(2, 1)` and
+    /// pacman standing next to it.
+    fn app(after_last: AfterLastLevel) -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default(), StatesPlugin))
+            .init_asset::<TileMap>()
+            .init_asset::<LevelPack>()
+            .init_state::<GameState>()
+            .init_resource::<CurrentLevel>()
+            .init_resource::<WallRendering>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .insert_resource(AsciiSheet {
+                image: default(),
+                layout: default(),
+                atlas: SpriteAtlas::builtin(),
+            })
+            .add_plugins((GameLoop, LevelPlugin));
+
+        let world = app.world_mut();
+        let levels = ["one", "two"]
+            .map(|name| {
+                let map = format!("name: {name}\n####\n#@.#\n####")
+                    .parse::<TileMap>()
+                    .unwrap();
+                Level {
+                    map: world.resource_mut::<Assets<TileMap>>().add(map),
+                    overrides: default(),
+                }
+            })
+            .to_vec();
+        let pack = world.resource_mut::<Assets<LevelPack>>().add(LevelPack {
+            name: None,
+            after_last,
+            levels,
+        });
+        world.insert_resource(LevelPackHandle(pack));
+        world.spawn((Pacman, GridPosition::new(1, 1)));
+
+        assert!(world.run_system_once(|mut levels: LevelStarter| levels.start(0)));
+        world
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::Playing);
+        app.update();
+        app
+    }
+
+    /// Let pacman eat the coin of the current level and run the app until the level is over.
+    fn clear_level(app: &mut App) {
+        let mut pacman = app
+            .world_mut()
+            .query_filtered::<&mut GridPosition, With<Pacman>>();
+        *pacman.single_mut(app.world_mut()) = GridPosition::new(2, 1);
+        app.update();
+        // Step back, so the coin of the next level stays where it is.
+        *pacman.single_mut(app.world_mut()) = GridPosition::new(1, 1);
+        app.update();
+        app.update();
+    }
+
+    fn state(app: &App) -> GameState {
+        *app.world().resource::<State<GameState>>().get()
+    }
+
+    fn map_name(app: &App) -> Option<String> {
+        app.world().resource::<TileMap>().meta().name.clone()
+    }
+
+    #[test]
+    fn test_next_level_and_victory() {
+        let mut app = app(AfterLastLevel::Victory);
+        assert_eq!(state(&app), GameState::Playing);
+        assert_eq!(map_name(&app).as_deref(), Some("one"));
+
+        clear_level(&mut app);
+        assert_eq!(state(&app), GameState::Playing);
+        assert_eq!(app.world().resource::<CurrentLevel>().0, 1);
+        assert_eq!(map_name(&app).as_deref(), Some("two"));
+
+        clear_level(&mut app);
+        assert_eq!(state(&app), GameState::Victory);
+        assert_eq!(app.world().resource::<CurrentLevel>().0, 1);
+    }
+
+    #[test]
+    fn test_wrap_after_last_level() {
+        let mut app = app(AfterLastLevel::Wrap);
+
+        clear_level(&mut app);
+        clear_level(&mut app);
+        assert_eq!(state(&app), GameState::Playing);
+        assert_eq!(app.world().resource::<CurrentLevel>().0, 0);
+        assert_eq!(map_name(&app).as_deref(), Some("one"));
+    }
+}
@@ -1,12 +1,15 @@
 use std::{sync::Arc, time::Duration};
 
-use bevy::{prelude::*, time::common_conditions::on_timer};
+use bevy::{app::AppExit, prelude::*, time::common_conditions::on_timer};
 use lighthouse_client::{
     protocol::{Authentication, Frame},
     Lighthouse, TokioWebSocket,
 };
 use tokio::{runtime::Runtime, sync::Mutex};
 
+/// Maximum time we wait for the connection and the runtime to shut down when the app exits.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
 #[derive(Debug, Clone)]
 pub struct LighthousePlugin {
     pub user: String,
@@ -35,13 +38,46 @@ impl Plugin for LighthousePlugin {
             app.insert_resource(LHWrapper(Arc::new(Mutex::new(lighthouse))));
             app.add_systems(
                 PostUpdate,
-                render_components.run_if(on_timer(Duration::from_secs_f64(1.0 / 60.0))),
+                render_components
+                    .run_if(resource_exists::<LHWrapper>)
+                    .run_if(on_timer(Duration::from_secs_f64(1.0 / 60.0))),
             );
         });
-        app.insert_resource(Rt(rt));
+        app.insert_resource(Rt(rt))
+            .add_systems(Last, disconnect_on_exit);
     }
 }
 
+/// Clear the lighthouse, close the websocket and shut down the runtime once the app is exiting,
+/// so the building is not left showing the last frame of the game.
+fn disconnect_on_exit(world: &mut World) {
+    if world.resource::<Events<AppExit>>().is_empty() {
+        return;
+    }
+
+    let Some(Rt(rt)) = world.remove_resource::<Rt>() else {
+        return;
+    };
+
+    if let Some(LHWrapper(lh)) = world.remove_resource::<LHWrapper>() {
+        let result = rt.block_on(tokio::time::timeout(SHUTDOWN_TIMEOUT, async move {
+            let mut lh = lh.lock().await;
+            if let Err(e) = lh.put_model(Frame::empty()).await {
+                error!("Error clearing lighthouse: {e}");
+            }
+            if let Err(e) = lh.close().await {
+                error!("Error closing lighthouse connection: {e}");
+            }
+        }));
+
+        if result.is_err() {
+            warn!("timed out while disconnecting from lighthouse");
+        }
+    }
+
+    rt.shutdown_timeout(SHUTDOWN_TIMEOUT);
+}
+
 fn render_components(
     rt: Res<Rt>,
     lh: Res<LHWrapper>,
@@ -50,7 +86,7 @@ fn render_components(
     let mut frame = Frame::empty();
 
     let mut entities = query.iter().collect::<Vec<_>>();
-    entities.sort_by(|(a, _), (b, _)| a.z.cmp(&b.z));
+    entities.sort_by_key(|(position, _)| position.z);
 
     for (LighthousePosition { x, y, .. }, color) in entities {
         let y = 13 - y.min(&13);
@@ -101,3 +137,10 @@ impl LighthouseColor {
         lighthouse_client::protocol::Color { red, green, blue }
     }
 }
+
+impl From<LighthouseColor> for Color {
+    fn from(value: LighthouseColor) -> Self {
+        let (r, g, b) = value.canonical();
+        Color::srgb_u8(r, g, b)
+    }
+}
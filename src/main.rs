@@ -1,18 +1,30 @@
 mod ascii;
+mod assets;
+mod bench;
+mod cli;
+mod editor;
 mod entities;
 mod gameloop;
+mod level;
 mod lighthouse;
 mod map;
+mod state;
 mod view;
 
-use ascii::load_ascii;
+use std::process::ExitCode;
+
+use ascii::{load_ascii, SpriteAtlas, ATLAS_PATH};
+use assets::BuiltinAssetsPlugin;
 use bevy::prelude::*;
 use dotenv::dotenv;
+use editor::EditorPlugin;
 use entities::*;
 use gameloop::GameLoop;
+use level::LevelPlugin;
 use lighthouse::LighthousePlugin;
-use map::MapPlugin;
-use view::{ViewConfigurationPlugin, SCREEN_HEIGHT, SCREEN_WIDTH};
+use map::{MapPlugin, WallRendering};
+use state::GameState;
+use view::ViewConfigurationPlugin;
 
 macro_rules! get_env {
     ($name:expr) => {
@@ -23,20 +35,46 @@ macro_rules! get_env {
     };
 }
 
-fn main() {
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if let Some(exit_code) = cli::run_command(&args) {
+        return exit_code;
+    }
+
     _ = dotenv().ok();
 
+    let wall_rendering = get_env!("WALL_RENDERING", "sprites")
+        .parse::<WallRendering>()
+        .unwrap_or_else(|e| {
+            eprintln!("{e}");
+            WallRendering::default()
+        });
+
+    let atlas = match SpriteAtlas::load() {
+        Ok(atlas) => atlas,
+        Err(e) => {
+            eprintln!("{ATLAS_PATH}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
     App::new()
+        .insert_resource(wall_rendering)
+        .insert_resource(atlas)
         .add_systems(PreStartup, load_ascii)
+        .add_plugins(BuiltinAssetsPlugin)
         .add_plugins(EntityPlugin)
-        .add_plugins(MapPlugin)
+        .add_plugins(MapPlugin {
+            pack: get_env!("LEVEL_PACK", "lighthouse.pack"),
+        })
+        .add_plugins(LevelPlugin)
         .add_plugins(ViewConfigurationPlugin)
         .add_plugins(GameLoop)
+        .add_plugins(EditorPlugin)
         .add_plugins(
             DefaultPlugins
                 .set(WindowPlugin {
                     primary_window: Some(Window {
-                        resolution: (SCREEN_WIDTH, SCREEN_HEIGHT).into(),
                         title: "Pacman Bevy".to_string(),
                         resizable: false,
                         ..default()
@@ -45,12 +83,15 @@ fn main() {
                 })
                 .set(ImagePlugin::default_nearest()),
         )
+        .init_state::<GameState>()
         .add_plugins(LighthousePlugin {
             token: get_env!("LH_TOKEN"),
             user: get_env!("LH_USER"),
         })
         .add_systems(Update, close_on_esc)
         .run();
+
+    ExitCode::SUCCESS
 }
 
 pub fn close_on_esc(
@@ -0,0 +1,60 @@
+/// Maps and level packs compiled into the binary, by their path relative to the assets folder.
+/// Files with the same path in the assets folder replace them.
+pub const BUILTIN_MAPS: [(&str, &str); 7] = [
+    (
+        "lighthouse.pack",
+        include_str!("../../assets/lighthouse.pack"),
+    ),
+    ("desktop.pack", include_str!("../../assets/desktop.pack")),
+    (
+        "lighthouse.txt",
+        include_str!("../../assets/lighthouse.txt"),
+    ),
+    (
+        "lighthouse-2.txt",
+        include_str!("../../assets/lighthouse-2.txt"),
+    ),
+    (
+        "lighthouse-3.txt",
+        include_str!("../../assets/lighthouse-3.txt"),
+    ),
+    ("map.txt", include_str!("../../assets/map.txt")),
+    ("desktop-2.txt", include_str!("../../assets/desktop-2.txt")),
+];
+
+/// Get the content of the built-in map (or level pack) with the given path.
+pub fn builtin_map(path: &str) -> Option<&'static str> {
+    BUILTIN_MAPS
+        .iter()
+        .find(|(name, _)| *name == path)
+        .map(|(_, content)| *content)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::map::{builtin_map, validate, LevelPackFile, TileMap, BUILTIN_MAPS};
+
+    #[test]
+    fn test_builtin_maps_are_playable() {
+        for (name, content) in BUILTIN_MAPS {
+            if !name.ends_with(".txt") {
+                continue;
+            }
+            let map = content.parse::<TileMap>().unwrap();
+            assert_eq!(validate(&map), Ok(()), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_builtin_packs_only_use_builtin_maps() {
+        for (name, content) in BUILTIN_MAPS {
+            if !name.ends_with(".pack") {
+                continue;
+            }
+            let pack = content.parse::<LevelPackFile>().unwrap();
+            for level in pack.levels {
+                assert!(builtin_map(&level.path).is_some(), "{name}: {}", level.path);
+            }
+        }
+    }
+}
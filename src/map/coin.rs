@@ -2,14 +2,15 @@ use bevy::{prelude::*, sprite::Anchor};
 
 use crate::{
     ascii::{AsciiSheet, SpriteIndices},
+    entities::GridPosition,
     lighthouse::{LighthouseBundle, LighthouseColor, LighthousePosition},
 };
 
 #[derive(Component)]
 pub struct Coin;
 
-/// Spawn a coin at the given location
-pub fn spawn_coin(commands: &mut Commands, ascii: &Res<AsciiSheet>, x: usize, y: usize) {
+/// Spawn a coin at the given location and return its entity
+pub fn spawn_coin(commands: &mut Commands, ascii: &Res<AsciiSheet>, x: usize, y: usize) -> Entity {
     let sprite = Sprite {
         custom_size: Some(Vec2::splat(1.0)),
         anchor: Anchor::BottomLeft,
@@ -17,21 +18,19 @@ pub fn spawn_coin(commands: &mut Commands, ascii: &Res<AsciiSheet>, x: usize, y:
     };
 
     let atlas = TextureAtlas {
-        index: SpriteIndices::SmallCoin.into(),
+        index: ascii.index(SpriteIndices::SmallCoin),
         layout: ascii.layout.clone(),
     };
 
     commands
         .spawn(Coin)
+        .insert(GridPosition::new(x as i32, y as i32))
         .insert((
             SpriteBundle {
                 sprite,
                 transform: Transform {
-                    translation: Vec3 {
-                        x: x as f32,
-                        y: y as f32,
-                        z: 1.0,
-                    },
+                    // x and y are derived from the grid position
+                    translation: Vec3::new(0.0, 0.0, 1.0),
                     scale: Vec3::new(1.0, 1.0, 0.0),
                     ..default()
                 },
@@ -41,7 +40,8 @@ pub fn spawn_coin(commands: &mut Commands, ascii: &Res<AsciiSheet>, x: usize, y:
             atlas,
         ))
         .insert(LighthouseBundle {
-            position: LighthousePosition { x, y, z: 1 },
+            position: LighthousePosition { x: 0, y: 0, z: 1 },
             color: LighthouseColor::Inline(169, 129, 98),
-        });
+        })
+        .id()
 }
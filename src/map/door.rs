@@ -0,0 +1,62 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+
+use crate::{
+    ascii::{AsciiSheet, SpriteIndices},
+    entities::GridPosition,
+    lighthouse::{LighthouseBundle, LighthouseColor, LighthousePosition},
+};
+
+#[derive(Component)]
+pub struct Door;
+
+/// Spawn a door at the given location and return its entity. A horizontal door connects the
+/// walls to its left and right.
+pub fn spawn_door(
+    commands: &mut Commands,
+    ascii: &Res<AsciiSheet>,
+    horizontal: bool,
+    x: usize,
+    y: usize,
+) -> Entity {
+    let sprite = Sprite {
+        custom_size: Some(Vec2::splat(1.0)),
+        color: Color::srgb(1.0, 0.72, 1.0),
+        ..default()
+    };
+
+    let atlas = TextureAtlas {
+        index: ascii.index(SpriteIndices::HouseWallStraight),
+        layout: ascii.layout.clone(),
+    };
+
+    commands
+        .spawn(Door)
+        .insert(GridPosition::new(x as i32, y as i32))
+        .insert(SpatialBundle {
+            // x and y are derived from the grid position
+            transform: Transform::from_xyz(0.0, 0.0, 1.0),
+            ..default()
+        })
+        .insert(LighthouseBundle {
+            position: LighthousePosition { x: 0, y: 0, z: 1 },
+            color: LighthouseColor::Inline(255, 184, 255),
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                SpriteBundle {
+                    sprite,
+                    transform: Transform {
+                        translation: Vec3::new(0.5, 0.5, 0.0),
+                        rotation: Quat::from_rotation_z(if horizontal { FRAC_PI_2 } else { 0.0 }),
+                        ..default()
+                    },
+                    texture: ascii.image.clone(),
+                    ..default()
+                },
+                atlas,
+            ));
+        })
+        .id()
+}
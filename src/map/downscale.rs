@@ -0,0 +1,386 @@
+use std::fmt;
+
+use bevy::prelude::*;
+
+use super::{validate, MapProblem, Tile, TileMap, Walker};
+
+/// Error returned, if a map can not be shrunk to the requested size without breaking it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownscaleFailed {
+    pub width: usize,
+    pub height: usize,
+    /// Size of the smallest map found on the way.
+    pub reached_width: usize,
+    pub reached_height: usize,
+}
+
+impl fmt::Display for DownscaleFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "can not shrink the map to {}x{} without breaking it, the smallest size found is {}x{}",
+            self.width, self.height, self.reached_width, self.reached_height
+        )
+    }
+}
+
+impl std::error::Error for DownscaleFailed {}
+
+/// Rows of a map from top to bottom, with markers written like in a map file.
+type Grid = Vec<Vec<char>>;
+
+/// Lines of a map, which are removed together.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Removal {
+    Row(usize),
+    /// One column or a column and its mirrored partner, so a symmetric map stays symmetric.
+    Columns(Vec<usize>),
+}
+
+impl Removal {
+    /// The cells of each removed line and of the lines next to it.
+    fn lines<T: Copy>(&self, grid: &[Vec<T>]) -> Vec<[Vec<T>; 3]> {
+        match self {
+            Removal::Row(y) => vec![[y - 1, *y, y + 1].map(|y| grid[y].clone())],
+            Removal::Columns(xs) => xs
+                .iter()
+                .map(|x| [x - 1, *x, x + 1].map(|x| grid.iter().map(|row| row[x]).collect()))
+                .collect(),
+        }
+    }
+
+    /// Number of tiles lost by the removal: lines equal to a line next to them can be removed
+    /// without changing the shape of the maze. Returns `None`, if a protected tile would be
+    /// removed.
+    fn loss(&self, grid: &Grid, protected: &[Vec<bool>]) -> Option<usize> {
+        if self
+            .lines(protected)
+            .iter()
+            .any(|[_, line, _]| line.contains(&true))
+        {
+            return None;
+        }
+
+        let mut loss = 0;
+
+        for [before, line, after] in self.lines(grid) {
+            let difference =
+                |other: &[char]| line.iter().zip(other).filter(|(a, b)| a != b).count();
+            loss += difference(&before).min(difference(&after));
+        }
+
+        Some(loss)
+    }
+
+    /// Remove the lines from the original x of every column and the original y of every row.
+    fn apply_to_lines(&self, columns: &mut Vec<u32>, rows: &mut Vec<u32>) {
+        match self {
+            Removal::Row(y) => {
+                rows.remove(rows.len() - 1 - y);
+            }
+            Removal::Columns(xs) => {
+                let mut x = 0;
+                columns.retain(|_| {
+                    x += 1;
+                    !xs.contains(&(x - 1))
+                });
+            }
+        }
+    }
+
+    fn apply(&self, grid: &Grid) -> Grid {
+        let mut grid = grid.clone();
+        match self {
+            Removal::Row(y) => {
+                grid.remove(*y);
+            }
+            Removal::Columns(xs) => {
+                for row in &mut grid {
+                    let mut x = 0;
+                    row.retain(|_| {
+                        x += 1;
+                        !xs.contains(&(x - 1))
+                    });
+                }
+            }
+        }
+        grid
+    }
+}
+
+fn grid(map: &TileMap) -> Grid {
+    (0..map.height())
+        .rev()
+        .map(|y| {
+            map.row(y)
+                .enumerate()
+                .map(
+                    |(x, tile)| match map.spawns().marker_at(UVec2::new(x as u32, y as u32)) {
+                        Some(marker) => marker.to_char(),
+                        None => tile.to_char(),
+                    },
+                )
+                .collect()
+        })
+        .collect()
+}
+
+/// Tiles, which must not be removed, in the same order as the [`Grid`]: all markers and
+/// everything around the ghost house, so it stays closed. The ghost house consists of its
+/// markers and the ghosts spawning next to them (or next to other ghosts inside of it).
+fn protected(map: &TileMap) -> Vec<Vec<bool>> {
+    let spawns = map.spawns();
+    let mut house = spawns.ghost_house.clone();
+    let mut index = 0;
+    while let Some(tile) = house.get(index).copied() {
+        index += 1;
+        for &(_, ghost) in &spawns.ghosts {
+            let touches = tile.x.abs_diff(ghost.x) <= 1 && tile.y.abs_diff(ghost.y) <= 1;
+            if touches && !house.contains(&ghost) {
+                house.push(ghost);
+            }
+        }
+    }
+
+    (0..map.height())
+        .rev()
+        .map(|y| {
+            (0..map.width())
+                .map(|x| {
+                    let position = UVec2::new(x as u32, y as u32);
+                    spawns.marker_at(position).is_some()
+                        || house.iter().any(|tile| {
+                            tile.x.abs_diff(position.x) <= 1 && tile.y.abs_diff(position.y) <= 1
+                        })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Whether the tiles of the map (ignoring markers) are mirrored at its vertical center line.
+fn is_symmetric(map: &TileMap) -> bool {
+    (0..map.height()).all(|y| {
+        let row = map.row(y).collect::<Vec<_>>();
+        row.iter().eq(row.iter().rev())
+    })
+}
+
+/// Problems found by the validator, with their positions translated to the map before any lines
+/// were removed. `columns` and `rows` hold the original x and y of every column and row.
+fn problems(map: &TileMap, columns: &[u32], rows: &[u32]) -> Vec<MapProblem> {
+    let original =
+        |position: UVec2| UVec2::new(columns[position.x as usize], rows[position.y as usize]);
+
+    validate(map)
+        .err()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|problem| match problem {
+            MapProblem::UnmatchedOpening { position, opposite } => MapProblem::UnmatchedOpening {
+                position: original(position),
+                opposite: original(opposite),
+            },
+            MapProblem::PacmanInWall { position } => MapProblem::PacmanInWall {
+                position: original(position),
+            },
+            MapProblem::UnreachableCoin { position } => MapProblem::UnreachableCoin {
+                position: original(position),
+            },
+            MapProblem::GhostWalledIn { ghost, position } => MapProblem::GhostWalledIn {
+                ghost,
+                position: original(position),
+            },
+        })
+        .collect()
+}
+
+/// Features a maze in the style of Pac-Man avoids: open areas of 2x2 walkable tiles and dead
+/// ends. Removing lines creates them, when corridors next to each other are merged.
+fn flaws(map: &TileMap) -> usize {
+    let open = |x: usize, y: usize| Walker::Pacman.can_enter(map[UVec2::new(x as u32, y as u32)]);
+
+    let open_areas = (1..map.width())
+        .flat_map(|x| (1..map.height()).map(move |y| (x, y)))
+        .filter(|(x, y)| open(*x, *y) && open(x - 1, *y) && open(*x, y - 1) && open(x - 1, y - 1))
+        .count();
+
+    let dead_ends = map
+        .iter()
+        .filter(|(position, tile)| {
+            let open_neighbours = map
+                .neighbours(*position, *tile == Tile::Tunnel)
+                .filter(|(_, tile)| Walker::Pacman.can_enter(*tile))
+                .count();
+            Walker::Pacman.can_enter(*tile) && open_neighbours <= 1
+        })
+        .count();
+
+    open_areas + dead_ends
+}
+
+/// Shrink a map until it fits into the given size by removing whole rows and columns, e.g., to
+/// play a large maze on the lighthouse.
+///
+/// The border, lines containing markers and the walls around the ghost house are always kept.
+/// Columns of a left-right symmetric map are removed in mirrored pairs (or only the center
+/// column), so the map stays symmetric. Every removal must not add any problems found by the
+/// validator, so all coins stay reachable. Among
+/// the possible removals, the one losing the fewest tiles and adding the fewest open areas and
+/// dead ends is chosen, which removes duplicate lines first.
+pub fn downscale(map: &TileMap, width: usize, height: usize) -> Result<TileMap, DownscaleFailed> {
+    let symmetric = is_symmetric(map);
+    let mut original_columns = (0..map.width() as u32).collect::<Vec<_>>();
+    let mut original_rows = (0..map.height() as u32).collect::<Vec<_>>();
+    let allowed_problems = problems(map, &original_columns, &original_rows);
+    let mut map = map.clone();
+
+    while map.width() > width || map.height() > height {
+        let grid = grid(&map);
+        let protected = protected(&map);
+        let (current_width, current_height) = (map.width(), map.height());
+        let flaws_before = flaws(&map);
+
+        let rows = (map.height() > height)
+            .then(|| (1..current_height.saturating_sub(1)).map(Removal::Row))
+            .into_iter()
+            .flatten();
+        let columns = (map.width() > width)
+            .then(|| {
+                (1..current_width.saturating_sub(1)).filter_map(move |x| {
+                    let mirrored = current_width - 1 - x;
+                    match symmetric {
+                        false => Some(Removal::Columns(vec![x])),
+                        true if x < mirrored => Some(Removal::Columns(vec![x, mirrored])),
+                        true if x == mirrored => Some(Removal::Columns(vec![x])),
+                        true => None,
+                    }
+                })
+            })
+            .into_iter()
+            .flatten();
+
+        let best = rows
+            .chain(columns)
+            .filter_map(|removal| {
+                let loss = removal.loss(&grid, &protected)?;
+                let text = removal
+                    .apply(&grid)
+                    .into_iter()
+                    .map(|row| row.into_iter().collect::<String>())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let mut candidate = text.parse::<TileMap>().ok()?;
+                let (mut columns, mut rows) = (original_columns.clone(), original_rows.clone());
+                removal.apply_to_lines(&mut columns, &mut rows);
+                let new_problem = problems(&candidate, &columns, &rows)
+                    .iter()
+                    .any(|problem| !allowed_problems.contains(problem));
+                if new_problem {
+                    return None;
+                }
+
+                *candidate.meta_mut() = map.meta().clone();
+                let damage = loss + 2 * flaws(&candidate).saturating_sub(flaws_before);
+                Some((damage, candidate, columns, rows))
+            })
+            .min_by_key(|(damage, ..)| *damage);
+
+        let Some((_, smaller, smaller_columns, smaller_rows)) = best else {
+            return Err(DownscaleFailed {
+                width,
+                height,
+                reached_width: current_width,
+                reached_height: current_height,
+            });
+        };
+        map = smaller;
+        original_columns = smaller_columns;
+        original_rows = smaller_rows;
+    }
+
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::map::{downscale, validate, DownscaleFailed, TileMap};
+
+    use super::{grid, is_symmetric};
+
+    /// The characters around the ghost house marker of the map.
+    fn ghost_house(map: &TileMap) -> Vec<String> {
+        let grid = grid(map);
+        let house = map.spawns().ghost_house[0];
+        let (x, row) = (house.x as usize, map.height() - 1 - house.y as usize);
+
+        grid[row - 1..=row + 2]
+            .iter()
+            .map(|line| line[x - 2..=x + 2].iter().collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_downscale_arcade_maze() {
+        let map = include_str!("../../assets/map.txt")
+            .parse::<TileMap>()
+            .unwrap();
+        let small = downscale(&map, 14, 14).unwrap();
+
+        assert!(small.width() <= 14 && small.height() <= 14);
+        assert_eq!(validate(&small), Ok(()));
+        assert!(is_symmetric(&small));
+        assert_eq!(small.spawns().ghosts.len(), map.spawns().ghosts.len());
+        assert_eq!(
+            small.spawns().ghost_house.len(),
+            map.spawns().ghost_house.len()
+        );
+        assert_eq!(small.meta(), map.meta());
+
+        // the ghosts still spawn inside of the closed ghost house
+        assert_eq!(ghost_house(&small), ["..B..", "##H##", "#IPC#", "## ##"]);
+        assert_eq!(ghost_house(&small), ghost_house(&map));
+    }
+
+    #[test]
+    fn test_duplicate_lines_are_removed_first() {
+        let map = "#######\n#.....#\n#.#.#.#\n#.#.#.#\n#.....#\n#..@..#\n#######"
+            .parse::<TileMap>()
+            .unwrap();
+
+        assert_eq!(
+            downscale(&map, 7, 6).unwrap(),
+            "#######\n#.....#\n#.#.#.#\n#.....#\n#..@..#\n#######"
+                .parse::<TileMap>()
+                .unwrap()
+        );
+        assert_eq!(downscale(&map, 7, 7).unwrap(), map);
+    }
+
+    #[test]
+    fn test_downscale_too_far() {
+        let map = "#####\n#.@.#\n#####".parse::<TileMap>().unwrap();
+
+        assert_eq!(
+            downscale(&map, 5, 2),
+            Err(DownscaleFailed {
+                width: 5,
+                height: 2,
+                reached_width: 5,
+                reached_height: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_problems_are_not_traded() {
+        // removing the middle row removes the unreachable coin on the right, but cuts off the coin
+        // at the top instead
+        let map = "#######\n#F#.###\n#...#.#\n#@#####\n#######"
+            .parse::<TileMap>()
+            .unwrap();
+        assert_eq!(validate(&map).unwrap_err().len(), 1);
+
+        assert!(downscale(&map, 7, 4).is_err());
+    }
+}
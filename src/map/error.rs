@@ -0,0 +1,278 @@
+use std::{error::Error, fmt, io};
+
+/// The different problems that can occur while parsing a map.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseMapErrorKind {
+    /// The map does not contain a single tile.
+    Empty,
+    /// The character does not represent any known tile.
+    UnknownTile(char),
+    /// A row has a different amount of tiles than the first row of the map.
+    RaggedRow { expected: usize, found: usize },
+    /// The header contains a key we do not know.
+    UnknownHeaderKey(String),
+    /// The value given for a key in the header is not valid for this key.
+    InvalidHeaderValue { key: String, value: String },
+    /// A marker, which may only occur once, is placed multiple times.
+    DuplicateMarker(char),
+    /// A marker, which is required, is missing in the map.
+    MissingMarker(char),
+    /// A teleporter does not have exactly one partner with the same number.
+    UnpairedTeleporter(char),
+}
+
+/// Error returned when the text representation of a map is invalid.
+/// `line` and `column` are 1-based and point at the offending character.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseMapError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseMapErrorKind,
+}
+
+impl ParseMapError {
+    pub fn new(line: usize, column: usize, kind: ParseMapErrorKind) -> Self {
+        Self { line, column, kind }
+    }
+}
+
+impl fmt::Display for ParseMapErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseMapErrorKind::Empty => write!(f, "map does not contain any tiles"),
+            ParseMapErrorKind::UnknownTile(character) => {
+                write!(f, "unknown tile character '{}'", character.escape_debug())
+            }
+            ParseMapErrorKind::RaggedRow { expected, found } => {
+                write!(f, "expected {expected} tiles in this row, found {found}")
+            }
+            ParseMapErrorKind::UnknownHeaderKey(key) => write!(f, "unknown header key '{key}'"),
+            ParseMapErrorKind::InvalidHeaderValue { key, value } => {
+                write!(f, "invalid value '{value}' for header key '{key}'")
+            }
+            ParseMapErrorKind::DuplicateMarker(marker) => {
+                write!(f, "marker '{marker}' may only be placed once")
+            }
+            ParseMapErrorKind::MissingMarker(marker) => {
+                write!(f, "map is missing the required marker '{marker}'")
+            }
+            ParseMapErrorKind::UnpairedTeleporter(teleporter) => {
+                write!(
+                    f,
+                    "teleporter '{teleporter}' has to be placed exactly twice"
+                )
+            }
+        }
+    }
+}
+
+impl fmt::Display for ParseMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.kind
+        )
+    }
+}
+
+impl Error for ParseMapError {}
+
+/// Error returned when a map could not be loaded.
+#[derive(Debug)]
+pub enum MapError {
+    Io(io::Error),
+    Parse(ParseMapError),
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapError::Io(e) => write!(f, "could not read map: {e}"),
+            MapError::Parse(e) => write!(f, "invalid map: {e}"),
+        }
+    }
+}
+
+impl Error for MapError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MapError::Io(e) => Some(e),
+            MapError::Parse(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for MapError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<ParseMapError> for MapError {
+    fn from(value: ParseMapError) -> Self {
+        Self::Parse(value)
+    }
+}
+
+/// The different problems that can occur while parsing a level pack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsePackErrorKind {
+    /// The pack does not list a single level.
+    NoLevels,
+    /// The header or a level uses a key we do not know.
+    UnknownKey(String),
+    /// The value given for a key is not valid for this key.
+    InvalidValue { key: String, value: String },
+    /// An override of a level is not given as `key=value`.
+    MalformedOverride(String),
+}
+
+/// Error returned when the text representation of a level pack is invalid.
+/// `line` is 1-based and points at the offending line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsePackError {
+    pub line: usize,
+    pub kind: ParsePackErrorKind,
+}
+
+impl ParsePackError {
+    pub fn new(line: usize, kind: ParsePackErrorKind) -> Self {
+        Self { line, kind }
+    }
+}
+
+impl fmt::Display for ParsePackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+
+        match &self.kind {
+            ParsePackErrorKind::NoLevels => write!(f, "pack does not contain any levels"),
+            ParsePackErrorKind::UnknownKey(key) => write!(f, "unknown key '{key}'"),
+            ParsePackErrorKind::InvalidValue { key, value } => {
+                write!(f, "invalid value '{value}' for key '{key}'")
+            }
+            ParsePackErrorKind::MalformedOverride(text) => {
+                write!(f, "expected an override like 'key=value', found '{text}'")
+            }
+        }
+    }
+}
+
+impl Error for ParsePackError {}
+
+/// Error returned when a level pack could not be loaded.
+#[derive(Debug)]
+pub enum PackError {
+    Io(io::Error),
+    Parse(ParsePackError),
+}
+
+impl fmt::Display for PackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackError::Io(e) => write!(f, "could not read level pack: {e}"),
+            PackError::Parse(e) => write!(f, "invalid level pack: {e}"),
+        }
+    }
+}
+
+impl Error for PackError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PackError::Io(e) => Some(e),
+            PackError::Parse(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for PackError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<ParsePackError> for PackError {
+    fn from(value: ParsePackError) -> Self {
+        Self::Parse(value)
+    }
+}
+
+/// Error returned when a map made with Tiled could not be imported.
+#[derive(Debug)]
+pub enum TiledError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Xml(xml::reader::Error),
+    /// The file is not a valid Tiled map (e.g., an attribute is missing).
+    Malformed(String),
+    /// The map uses a feature of Tiled we do not support.
+    Unsupported(String),
+    /// A tile with this global id is used, which has no valid `tile` property.
+    UnknownTile(u32),
+    /// An object has a type (or name), which is not a marker.
+    UnknownObject(String),
+    /// An object is placed outside of the tile layer.
+    ObjectOutsideMap(String),
+    /// A custom property of the map is not a valid header entry.
+    Property(ParseMapErrorKind),
+    /// The converted map is not valid.
+    Map(ParseMapError),
+}
+
+impl fmt::Display for TiledError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TiledError::Io(e) => write!(f, "could not read map: {e}"),
+            TiledError::Json(e) => write!(f, "invalid Tiled map: {e}"),
+            TiledError::Xml(e) => write!(f, "invalid Tiled map: {e}"),
+            TiledError::Malformed(message) => write!(f, "invalid Tiled map: {message}"),
+            TiledError::Unsupported(feature) => write!(f, "{feature} are not supported"),
+            TiledError::UnknownTile(gid) => {
+                write!(f, "tile {gid} does not have a valid 'tile' property")
+            }
+            TiledError::UnknownObject(kind) => write!(f, "unknown object '{kind}'"),
+            TiledError::ObjectOutsideMap(kind) => {
+                write!(f, "object '{kind}' is placed outside of the map")
+            }
+            TiledError::Property(kind) => write!(f, "invalid map property: {kind}"),
+            TiledError::Map(e) => write!(f, "invalid map: {e}"),
+        }
+    }
+}
+
+impl Error for TiledError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TiledError::Io(e) => Some(e),
+            TiledError::Json(e) => Some(e),
+            TiledError::Xml(e) => Some(e),
+            TiledError::Map(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for TiledError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<serde_json::Error> for TiledError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Json(value)
+    }
+}
+
+impl From<xml::reader::Error> for TiledError {
+    fn from(value: xml::reader::Error) -> Self {
+        Self::Xml(value)
+    }
+}
+
+impl From<ParseMapError> for TiledError {
+    fn from(value: ParseMapError) -> Self {
+        Self::Map(value)
+    }
+}
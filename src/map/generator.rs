@@ -0,0 +1,478 @@
+use std::{collections::HashSet, fmt};
+
+use bevy::prelude::*;
+
+use crate::entities::GhostType;
+
+use super::{Column, MapMeta, SpawnPoints, Tile, TileMap};
+
+/// Smallest width a generated maze can have (enough for two cells per half and a ghost house).
+pub const MIN_MAZE_WIDTH: usize = 9;
+
+/// Smallest height a generated maze can have (a row below, at and above the ghost house).
+pub const MIN_MAZE_HEIGHT: usize = 7;
+
+/// Error returned, if a maze of the requested size can not be generated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MazeTooSmall {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl fmt::Display for MazeTooSmall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "can not generate a {}x{} maze, it has to be at least {MIN_MAZE_WIDTH}x{MIN_MAZE_HEIGHT}",
+            self.width, self.height
+        )
+    }
+}
+
+impl std::error::Error for MazeTooSmall {}
+
+/// Small, deterministic random number generator (SplitMix64), so the same seed always results in
+/// the same maze.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Random number in `0..bound`.
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// Coordinates of cells (i.e., crossings of corridors) from `start` to `end`. Cells are two tiles
+/// apart, only one step is three tiles wide, if the distance can not be split into steps of two.
+fn lattice(start: usize, end: usize) -> Vec<usize> {
+    let distance = end - start;
+    let steps = distance / 2;
+    let wide_step = (distance % 2 == 1).then_some(steps / 2);
+
+    let mut coordinates = vec![start];
+    let mut current = start;
+    for step in 0..steps {
+        current += if Some(step) == wide_step { 3 } else { 2 };
+        if current <= end {
+            coordinates.push(current);
+        }
+    }
+    coordinates
+}
+
+/// Maze on a lattice of cells, of which only the left half is generated and then mirrored.
+struct Maze {
+    width: usize,
+    height: usize,
+    /// x coordinates of the cells in the left half
+    xs: Vec<usize>,
+    /// y coordinates of the cells
+    ys: Vec<usize>,
+    /// whether the rightmost cell column lies exactly in the center of the map
+    centered: bool,
+    /// open links between neighbouring cells (given as indices into `xs` and `ys`)
+    links: HashSet<((usize, usize), (usize, usize))>,
+    /// rows (indices into `ys`) in which the rightmost cell is connected to its mirrored cell
+    cross_links: HashSet<usize>,
+    /// cells occupied by the ghost house
+    house: HashSet<(usize, usize)>,
+}
+
+impl Maze {
+    fn new(width: usize, height: usize) -> Self {
+        // the rightmost cell is either in the center or leaves one (odd width) or two (even
+        // width) tiles to its mirrored cell
+        let center = (width - 1) / 2;
+        let last = if width % 2 == 1 && center % 2 == 1 {
+            center
+        } else if width % 2 == 1 {
+            center - 1
+        } else {
+            (width - 4) / 2
+        };
+
+        let xs = lattice(1, last);
+        let ys = lattice(1, height - 2);
+
+        Self {
+            width,
+            height,
+            centered: *xs.last().unwrap() == center,
+            xs,
+            ys,
+            links: HashSet::new(),
+            cross_links: HashSet::new(),
+            house: HashSet::new(),
+        }
+    }
+
+    fn link_key(a: (usize, usize), b: (usize, usize)) -> ((usize, usize), (usize, usize)) {
+        (a.min(b), a.max(b))
+    }
+
+    /// Neighbouring cells within the left half, which are not part of the ghost house.
+    fn neighbours(&self, (i, j): (usize, usize)) -> Vec<(usize, usize)> {
+        let mut neighbours = vec![];
+        if i > 0 {
+            neighbours.push((i - 1, j));
+        }
+        if i + 1 < self.xs.len() {
+            neighbours.push((i + 1, j));
+        }
+        if j > 0 {
+            neighbours.push((i, j - 1));
+        }
+        if j + 1 < self.ys.len() {
+            neighbours.push((i, j + 1));
+        }
+        neighbours.retain(|cell| !self.house.contains(cell));
+        neighbours
+    }
+
+    fn is_last_column(&self, i: usize) -> bool {
+        i + 1 == self.xs.len()
+    }
+
+    /// Number of open connections of a cell, counting both sides for cells in the center.
+    fn degree(&self, cell: (usize, usize)) -> usize {
+        let mut degree = self
+            .neighbours(cell)
+            .into_iter()
+            .filter(|other| self.links.contains(&Self::link_key(cell, *other)))
+            .count();
+
+        if self.is_last_column(cell.0) {
+            if self.centered {
+                // the link to the left is mirrored to the right
+                if cell.0 > 0
+                    && self
+                        .links
+                        .contains(&Self::link_key(cell, (cell.0 - 1, cell.1)))
+                {
+                    degree += 1;
+                }
+            } else if self.cross_links.contains(&cell.1) {
+                degree += 1;
+            }
+        }
+
+        degree
+    }
+
+    /// Carve a spanning tree through all cells with a randomized depth-first search.
+    fn carve(&mut self, rng: &mut Rng) {
+        let start = (0, 0);
+        let mut visited = HashSet::from([start]);
+        let mut stack = vec![start];
+
+        while let Some(&cell) = stack.last() {
+            let mut candidates = self
+                .neighbours(cell)
+                .into_iter()
+                .filter(|other| !visited.contains(other))
+                .collect::<Vec<_>>();
+
+            if candidates.is_empty() {
+                stack.pop();
+                continue;
+            }
+
+            rng.shuffle(&mut candidates);
+            let next = candidates[0];
+            self.links.insert(Self::link_key(cell, next));
+            visited.insert(next);
+            stack.push(next);
+        }
+    }
+
+    /// Open additional links until no cell is a dead end anymore.
+    fn remove_dead_ends(&mut self, rng: &mut Rng) {
+        let mut cells = (0..self.xs.len())
+            .flat_map(|i| (0..self.ys.len()).map(move |j| (i, j)))
+            .filter(|cell| !self.house.contains(cell))
+            .collect::<Vec<_>>();
+        rng.shuffle(&mut cells);
+
+        for cell in cells {
+            if self.degree(cell) >= 2 {
+                continue;
+            }
+
+            let mut candidates = self
+                .neighbours(cell)
+                .into_iter()
+                .filter(|other| !self.links.contains(&Self::link_key(cell, *other)))
+                .map(Some)
+                .collect::<Vec<_>>();
+            if self.is_last_column(cell.0) && !self.centered && !self.cross_links.contains(&cell.1)
+            {
+                // `None` stands for the link to the mirrored cell
+                candidates.push(None);
+            }
+            rng.shuffle(&mut candidates);
+
+            // prefer connecting two dead ends with each other
+            let choice = candidates
+                .iter()
+                .find(|candidate| candidate.is_some_and(|other| self.degree(other) < 2))
+                .or(candidates.first())
+                .copied();
+
+            match choice {
+                Some(Some(other)) => {
+                    self.links.insert(Self::link_key(cell, other));
+                }
+                Some(None) => {
+                    self.cross_links.insert(cell.1);
+                }
+                None => {}
+            }
+        }
+    }
+
+    fn mirror(&self, x: usize) -> usize {
+        self.width - 1 - x
+    }
+
+    /// Open all tiles on the straight line between two positions (on both halves).
+    fn open(&self, tiles: &mut [Column], (x1, y1): (usize, usize), (x2, y2): (usize, usize)) {
+        for x in x1.min(x2)..=x1.max(x2) {
+            for column in [x, self.mirror(x)] {
+                tiles[column][y1.min(y2)..=y1.max(y2)].fill(Tile::Coin);
+            }
+        }
+    }
+
+    fn position(&self, (i, j): (usize, usize)) -> (usize, usize) {
+        (self.xs[i], self.ys[j])
+    }
+
+    /// Convert the cells and links into tiles. Every open tile initially contains a coin.
+    fn tiles(&self) -> Vec<Column> {
+        let mut tiles = vec![vec![Tile::Wall; self.height]; self.width];
+
+        for i in 0..self.xs.len() {
+            for j in 0..self.ys.len() {
+                if !self.house.contains(&(i, j)) {
+                    let position = self.position((i, j));
+                    self.open(&mut tiles, position, position);
+                }
+            }
+        }
+
+        for (a, b) in &self.links {
+            self.open(&mut tiles, self.position(*a), self.position(*b));
+        }
+
+        let last = self.xs.len() - 1;
+        for j in &self.cross_links {
+            let (x, y) = self.position((last, *j));
+            self.open(&mut tiles, (x, y), (self.mirror(x), y));
+        }
+
+        tiles
+    }
+}
+
+/// Generate a left-right symmetric maze in the style of Pac-Man with the given size. The maze has
+/// no dead ends (except for the ghost house), a ghost house in its center and at least one tunnel
+/// to wrap around the map. The same seed always results in the same maze.
+pub fn generate_maze(width: usize, height: usize, seed: u64) -> Result<TileMap, MazeTooSmall> {
+    if width < MIN_MAZE_WIDTH || height < MIN_MAZE_HEIGHT {
+        return Err(MazeTooSmall { width, height });
+    }
+
+    let mut rng = Rng(seed);
+    let mut maze = Maze::new(width, height);
+
+    // the ghost house occupies the rightmost cells of the middle row, so it is at least three
+    // tiles wide
+    let house_row = maze.ys.len() / 2;
+    let last = maze.xs.len() - 1;
+    let house_start = if maze.centered { last - 1 } else { last };
+    for i in house_start..=last {
+        maze.house.insert((i, house_row));
+    }
+
+    // pacman starts below the ghost house and blinky above it, both in the center of the map
+    if !maze.centered {
+        maze.cross_links.insert(house_row - 1);
+        maze.cross_links.insert(house_row + 1);
+    }
+
+    maze.carve(&mut rng);
+    maze.remove_dead_ends(&mut rng);
+
+    let mut tiles = maze.tiles();
+    let mut spawns = SpawnPoints::default();
+
+    let center = (width - 1) / 2;
+    let house_y = maze.ys[house_row];
+    let above_house = maze.ys[house_row + 1];
+    let below_house = maze.ys[house_row - 1];
+    let house_left = maze.xs[house_start];
+    let house_right = maze.mirror(house_left);
+
+    // ghost house with the door at the top
+    maze.open(&mut tiles, (house_left, house_y), (house_right, house_y));
+    maze.open(&mut tiles, (center, house_y + 1), (center, above_house - 1));
+
+    let ghosts = [
+        (
+            GhostType::Blinky,
+            UVec2::new(center as u32, above_house as u32),
+        ),
+        (
+            GhostType::Inky,
+            UVec2::new(house_left as u32, house_y as u32),
+        ),
+        (GhostType::Pinky, UVec2::new(center as u32, house_y as u32)),
+        (
+            GhostType::Clyde,
+            UVec2::new(house_right as u32, house_y as u32),
+        ),
+    ];
+    spawns.pacman = UVec2::new(center as u32, below_house as u32);
+    spawns.ghosts = ghosts.to_vec();
+
+    for (x, column) in tiles
+        .iter()
+        .enumerate()
+        .take(house_right + 1)
+        .skip(house_left)
+    {
+        for (y, tile) in column.iter().enumerate().take(above_house).skip(house_y) {
+            let position = UVec2::new(x as u32, y as u32);
+            if *tile != Tile::Wall && spawns.marker_at(position).is_none() {
+                spawns.ghost_house.push(position);
+            }
+        }
+    }
+
+    // tunnels through the left and right border in rows other than the ghost house
+    let mut tunnel_rows = (0..maze.ys.len())
+        .filter(|j| *j != house_row)
+        .collect::<Vec<_>>();
+    rng.shuffle(&mut tunnel_rows);
+    let tunnels = if tunnel_rows.len() >= 6 { 2 } else { 1 };
+    for j in tunnel_rows.into_iter().take(tunnels) {
+        let y = maze.ys[j];
+        maze.open(&mut tiles, (0, y), (0, y));
+        tiles[0][y] = Tile::Tunnel;
+        tiles[width - 1][y] = Tile::Tunnel;
+    }
+
+    // no coins where pacman and the ghosts start or inside of the ghost house
+    for position in spawns
+        .ghost_house
+        .iter()
+        .chain(ghosts.iter().map(|(_, position)| position))
+        .chain([&spawns.pacman])
+    {
+        tiles[position.x as usize][position.y as usize] = Tile::Empty;
+    }
+
+    let meta = MapMeta {
+        name: Some(format!("Generated {width}x{height} ({seed})")),
+        ..default()
+    };
+
+    Ok(TileMap::new(tiles, spawns, meta))
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::UVec2;
+
+    use crate::map::{generate_maze, validate, MazeTooSmall, Tile};
+
+    const SIZES: [(usize, usize); 6] = [(9, 7), (10, 9), (14, 14), (17, 15), (19, 22), (28, 31)];
+
+    #[test]
+    fn test_generated_mazes_are_playable() {
+        for (width, height) in SIZES {
+            for seed in 0..20 {
+                let map = generate_maze(width, height, seed).unwrap();
+                assert_eq!((map.width(), map.height()), (width, height));
+
+                assert_eq!(validate(&map), Ok(()), "{width}x{height} ({seed}):\n{map}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_generated_mazes_are_symmetric() {
+        for (width, height) in SIZES {
+            let map = generate_maze(width, height, 42).unwrap();
+
+            for x in 0..width as i32 {
+                for y in 0..height as i32 {
+                    assert_eq!(
+                        map.at(x, y) == Some(Tile::Wall),
+                        map.at(width as i32 - 1 - x, y) == Some(Tile::Wall)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_generated_mazes_have_no_dead_ends() {
+        for (width, height) in SIZES {
+            for seed in 0..20 {
+                let map = generate_maze(width, height, seed).unwrap();
+
+                for x in 0..width as i32 {
+                    for y in 0..height as i32 {
+                        let in_house = map
+                            .spawns()
+                            .ghost_house
+                            .iter()
+                            .chain(map.spawns().ghosts.iter().map(|(_, position)| position))
+                            .any(|position| position.as_ivec2() == (x, y).into());
+                        if in_house || map.at(x, y) == Some(Tile::Wall) {
+                            continue;
+                        }
+
+                        let exits = map
+                            .neighbours(UVec2::new(x as u32, y as u32), true)
+                            .filter(|(_, tile)| *tile != Tile::Wall)
+                            .count();
+                        assert!(exits >= 2, "dead end at ({x}, {y}):\n{map}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_generation_is_deterministic() {
+        assert_eq!(generate_maze(14, 14, 7), generate_maze(14, 14, 7));
+        assert_ne!(generate_maze(14, 14, 7), generate_maze(14, 14, 8));
+    }
+
+    #[test]
+    fn test_too_small() {
+        assert_eq!(
+            generate_maze(8, 14, 0),
+            Err(MazeTooSmall {
+                width: 8,
+                height: 14
+            })
+        );
+    }
+}
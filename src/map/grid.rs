@@ -0,0 +1,258 @@
+use bevy::prelude::*;
+
+use crate::entities::{GridPosition, MovementDirection};
+
+use super::{Tile, TileMap, Walker};
+
+/// A single tile of the grid together with the entity representing it (if any).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cell {
+    tile: Tile,
+    entity: Option<Entity>,
+    /// Position of the paired teleporter, if this tile is a teleporter.
+    teleport_target: Option<UVec2>,
+}
+
+/// Occupancy of every tile of the current map, which allows looking up walls and coins (and the
+/// entities representing them) by their position instead of scanning all their entities. It is
+/// built whenever the tiles of the map are spawned and kept in sync when coins are eaten or tiles
+/// are changed.
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct Grid {
+    width: usize,
+    height: usize,
+    /// Cells row by row, starting at the bottom left.
+    cells: Vec<Cell>,
+    /// Number of coins left on the grid, so clearing the level can be detected without scanning
+    /// all cells.
+    coins_left: usize,
+}
+
+impl From<&TileMap> for Grid {
+    fn from(map: &TileMap) -> Self {
+        let width = map.width();
+        let height = map.height();
+        let mut cells = vec![
+            Cell {
+                tile: Tile::Empty,
+                entity: None,
+                teleport_target: None,
+            };
+            width * height
+        ];
+
+        for (position, tile) in map.iter() {
+            let cell = &mut cells[position.y as usize * width + position.x as usize];
+            cell.tile = tile;
+            cell.teleport_target = map.teleport_target(position.x as i32, position.y as i32);
+        }
+
+        Self {
+            width,
+            height,
+            coins_left: map.count(Tile::Coin),
+            cells,
+        }
+    }
+}
+
+impl Grid {
+    /// Number of columns of the grid.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Number of rows of the grid.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return None;
+        }
+        Some(y as usize * self.width + x as usize)
+    }
+
+    /// Get the tile at the specified position. Returns `None` outside of the grid.
+    pub fn tile(&self, x: i32, y: i32) -> Option<Tile> {
+        self.index(x, y).map(|index| self.cells[index].tile)
+    }
+
+    /// Check, whether the walker may move onto the specified position.
+    pub fn is_walkable(&self, x: i32, y: i32, walker: Walker) -> bool {
+        self.tile(x, y).is_some_and(|tile| walker.can_enter(tile))
+    }
+
+    /// Check, whether the walker may move from one position onto the other one in the given
+    /// direction, which also has to be allowed by one-way tiles on both of them.
+    pub fn can_move(
+        &self,
+        from: GridPosition,
+        to: GridPosition,
+        direction: MovementDirection,
+        walker: Walker,
+    ) -> bool {
+        let allows = |position: GridPosition| {
+            self.tile(position.x, position.y)
+                .is_none_or(|tile| tile.allows(direction))
+        };
+        self.is_walkable(to.x, to.y, walker) && allows(from) && allows(to)
+    }
+
+    /// Get the position of the teleporter paired with the teleporter at the specified position.
+    pub fn teleport_target(&self, x: i32, y: i32) -> Option<UVec2> {
+        self.index(x, y)
+            .and_then(|index| self.cells[index].teleport_target)
+    }
+
+    /// Remember the entity representing the tile at the specified position.
+    pub fn set_entity(&mut self, x: i32, y: i32, entity: Entity) {
+        if let Some(index) = self.index(x, y) {
+            self.cells[index].entity = Some(entity);
+        }
+    }
+
+    /// Forget the entity representing the tile at the specified position and return it.
+    pub fn take_entity(&mut self, x: i32, y: i32) -> Option<Entity> {
+        let index = self.index(x, y)?;
+        self.cells[index].entity.take()
+    }
+
+    /// Update the tile at the specified position to the one of the map. If a teleporter is added
+    /// or removed, the targets of all teleporters are updated as well.
+    pub fn update_tile(&mut self, map: &TileMap, x: i32, y: i32) {
+        let (Some(index), Some(tile)) = (self.index(x, y), map.at(x, y)) else {
+            return;
+        };
+        let old = std::mem::replace(&mut self.cells[index].tile, tile);
+        if old == Tile::Coin {
+            self.coins_left -= 1;
+        }
+        if tile == Tile::Coin {
+            self.coins_left += 1;
+        }
+
+        if !matches!(old, Tile::Teleporter(_)) && !matches!(tile, Tile::Teleporter(_)) {
+            return;
+        }
+        for index in 0..self.cells.len() {
+            let (x, y) = ((index % self.width) as i32, (index / self.width) as i32);
+            self.cells[index].teleport_target = map.teleport_target(x, y);
+        }
+    }
+
+    /// Number of coins left on the grid.
+    pub fn coins_left(&self) -> usize {
+        self.coins_left
+    }
+
+    /// Remove the coin at the specified position (if there is one) and return its entity.
+    pub fn take_coin(&mut self, x: i32, y: i32) -> Option<Entity> {
+        let index = self.index(x, y)?;
+        let cell = &mut self.cells[index];
+        if cell.tile != Tile::Coin {
+            return None;
+        }
+
+        cell.tile = Tile::Empty;
+        self.coins_left -= 1;
+        cell.entity.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{ecs::entity::Entity, math::UVec2};
+
+    use crate::map::{Grid, Tile, TileMap, Walker};
+
+    #[test]
+    fn test_grid_from_map() {
+        let map = "####\n#.@#\n####".parse::<TileMap>().unwrap();
+        let grid = Grid::from(&map);
+
+        assert_eq!((grid.width(), grid.height()), (4, 3));
+        assert_eq!(grid.tile(1, 1), Some(Tile::Coin));
+        assert_eq!(grid.tile(4, 1), None);
+        assert!(grid.is_walkable(2, 1, Walker::Pacman));
+        assert!(!grid.is_walkable(0, 1, Walker::Pacman));
+        assert!(!grid.is_walkable(-1, 1, Walker::Pacman));
+
+        let map = "####\n#-@#\n####".parse::<TileMap>().unwrap();
+        let grid = Grid::from(&map);
+        assert!(!grid.is_walkable(1, 1, Walker::Pacman));
+        assert!(grid.is_walkable(1, 1, Walker::Ghost));
+    }
+
+    #[test]
+    fn test_teleport_target() {
+        let map = "#####\n#4@4#\n#####".parse::<TileMap>().unwrap();
+        let grid = Grid::from(&map);
+
+        assert_eq!(grid.teleport_target(1, 1), Some(UVec2::new(3, 1)));
+        assert_eq!(grid.teleport_target(3, 1), Some(UVec2::new(1, 1)));
+        assert_eq!(grid.teleport_target(2, 1), None);
+    }
+
+    #[test]
+    fn test_update_tile() {
+        let mut map = "######\n#1@.1#\n######".parse::<TileMap>().unwrap();
+        let mut grid = Grid::from(&map);
+
+        map.set(3, 1, Tile::Wall);
+        grid.update_tile(&map, 3, 1);
+        assert!(!grid.is_walkable(3, 1, Walker::Pacman));
+
+        map.set(1, 1, Tile::Empty);
+        grid.update_tile(&map, 1, 1);
+        assert_eq!(grid.tile(1, 1), Some(Tile::Empty));
+        assert_eq!(grid.teleport_target(1, 1), None);
+        assert_eq!(grid.teleport_target(4, 1), None);
+    }
+
+    #[test]
+    fn test_take_coin() {
+        let map = "####\n#.@#\n####".parse::<TileMap>().unwrap();
+        let mut grid = Grid::from(&map);
+        let coin = Entity::from_raw(42);
+        grid.set_entity(1, 1, coin);
+
+        assert_eq!(grid.take_coin(2, 1), None);
+        assert_eq!(grid.coins_left(), 1);
+        assert_eq!(grid.take_coin(1, 1), Some(coin));
+        assert_eq!(grid.tile(1, 1), Some(Tile::Empty));
+        assert_eq!(grid.coins_left(), 0);
+        assert_eq!(grid.take_coin(1, 1), None);
+    }
+
+    #[test]
+    fn test_coins_left() {
+        let mut map = "#####\n#..@#\n#.-.#\n#####".parse::<TileMap>().unwrap();
+        let mut grid = Grid::from(&map);
+        assert_eq!(grid.coins_left(), 4);
+
+        grid.take_coin(1, 1);
+        grid.take_coin(1, 1);
+        grid.take_coin(2, 1);
+        assert_eq!(grid.coins_left(), 3);
+
+        // replacing a coin, adding a coin and changing other tiles
+        map.set(3, 1, Tile::Wall);
+        grid.update_tile(&map, 3, 1);
+        map.set(3, 2, Tile::Coin);
+        grid.update_tile(&map, 3, 2);
+        map.set(2, 1, Tile::Empty);
+        grid.update_tile(&map, 2, 1);
+        assert_eq!(grid.coins_left(), 3);
+
+        // an eaten coin is put back, as the map still has it
+        grid.update_tile(&map, 1, 1);
+        assert_eq!(grid.coins_left(), 4);
+
+        for (x, y) in [(1, 1), (1, 2), (2, 2), (3, 2)] {
+            grid.take_coin(x, y);
+        }
+        assert_eq!(grid.coins_left(), 0);
+    }
+}
@@ -0,0 +1,28 @@
+use bevy::asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext};
+
+use super::{MapError, TileMap};
+
+/// Loader for maps in our text format, so they can be loaded through the `AssetServer`.
+#[derive(Default)]
+pub struct TileMapLoader;
+
+impl AssetLoader for TileMapLoader {
+    type Asset = TileMap;
+    type Settings = ();
+    type Error = MapError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut content = String::new();
+        reader.read_to_string(&mut content).await?;
+        Ok(content.parse()?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["txt"]
+    }
+}
@@ -0,0 +1,87 @@
+use std::fmt;
+
+use crate::lighthouse::LighthouseColor;
+
+use super::ParseMapErrorKind;
+
+/// Optional information about a map, given in the header of a map file.
+///
+/// The header consists of `key: value` lines in front of the tiles, e.g.:
+///
+/// ```text
+/// name: Lighthouse
+/// author: Somebody
+/// wall_color: #0000ff
+/// tick_speed: 2
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MapMeta {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    /// Color of the walls on the screen and on the lighthouse.
+    pub wall_color: Option<LighthouseColor>,
+    /// Number of tiles pacman moves per second.
+    pub tick_speed: Option<f64>,
+}
+
+impl MapMeta {
+    /// Set the value for a key of the header.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), ParseMapErrorKind> {
+        let invalid = || ParseMapErrorKind::InvalidHeaderValue {
+            key: key.to_string(),
+            value: value.to_string(),
+        };
+
+        match key {
+            "name" => self.name = Some(value.to_string()),
+            "author" => self.author = Some(value.to_string()),
+            "wall_color" => self.wall_color = Some(parse_hex_color(value).ok_or_else(invalid)?),
+            "tick_speed" => {
+                let speed = value.parse::<f64>().map_err(|_| invalid())?;
+                if !speed.is_finite() || speed <= 0.0 {
+                    return Err(invalid());
+                }
+                self.tick_speed = Some(speed);
+            }
+            _ => return Err(ParseMapErrorKind::UnknownHeaderKey(key.to_string())),
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for MapMeta {
+    /// Write the header in the format expected by the map parser.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(name) = &self.name {
+            writeln!(f, "name: {name}")?;
+        }
+        if let Some(author) = &self.author {
+            writeln!(f, "author: {author}")?;
+        }
+        if let Some(color) = self.wall_color {
+            let (r, g, b) = color.canonical();
+            writeln!(f, "wall_color: #{r:02x}{g:02x}{b:02x}")?;
+        }
+        if let Some(speed) = self.tick_speed {
+            writeln!(f, "tick_speed: {speed}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse a color in the form `#rrggbb`.
+fn parse_hex_color(value: &str) -> Option<LighthouseColor> {
+    let hex = value.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+
+    let channel = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).ok();
+
+    Some(LighthouseColor::Explicit {
+        r: channel(0)?,
+        g: channel(2)?,
+        b: channel(4)?,
+    })
+}
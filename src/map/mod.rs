@@ -1,42 +1,417 @@
+mod builtin;
 mod coin;
+mod door;
+mod downscale;
+mod error;
+mod generator;
+mod grid;
+mod loader;
+mod meta;
+mod navigation;
+mod pack;
+mod spawn;
+mod special;
+mod teleporter;
 mod tile;
+mod tiled;
 mod tilemap;
+mod validation;
 mod wall;
 
+pub use builtin::*;
 pub use coin::*;
+pub use door::*;
+pub use downscale::*;
+pub use error::*;
+pub use generator::*;
+pub use grid::*;
+pub use loader::*;
+pub use meta::*;
+pub use navigation::*;
+pub use pack::*;
+pub use spawn::*;
+pub use special::*;
+pub use teleporter::*;
 pub use tile::*;
+pub use tiled::*;
 pub use tilemap::*;
+pub use validation::*;
 pub use wall::*;
 
-use bevy::prelude::*;
+use bevy::{asset::AssetLoadFailedEvent, ecs::system::SystemParam, prelude::*};
 
-use crate::ascii::AsciiSheet;
-
-pub struct MapPlugin;
+use crate::{ascii::AsciiSheet, state::GameState};
 
 /// Plugin for managing the map load and instantiation of tiles.
+pub struct MapPlugin {
+    /// Path of the level pack to play, relative to the assets folder.
+    pub pack: String,
+}
+
 impl Plugin for MapPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(TileMap::from_string("assets/lighthouse.txt"))
-            .add_systems(Startup, spawn_tiles);
-    }
-}
-
-/// Spawn tiles depending on the loaded map.
-fn spawn_tiles(mut commands: Commands, map: Res<TileMap>, ascii: Res<AsciiSheet>) {
-    let columns = map.columns();
-
-    for (x, column) in columns.enumerate() {
-        for (y, tile) in column.iter().enumerate() {
-            match *tile {
-                Tile::Wall => {
-                    spawn_sprites_for_wall(&mut commands, &ascii, &map, x as i32, y as i32);
-                }
-                Tile::Coin => spawn_coin(&mut commands, &ascii, x, y),
-                _ => {
-                    continue;
-                }
-            };
+        let pack = self.pack.clone();
+
+        app.init_asset::<TileMap>()
+            .init_asset::<LevelPack>()
+            .init_asset_loader::<TileMapLoader>()
+            .init_asset_loader::<TiledMapLoader>()
+            .init_asset_loader::<LevelPackLoader>()
+            .add_event::<SetTile>()
+            .init_resource::<WallRendering>()
+            .init_resource::<CurrentLevel>()
+            .add_systems(
+                Startup,
+                move |mut commands: Commands, assets: Res<AssetServer>| {
+                    commands.insert_resource(LevelPackHandle(assets.load(pack.clone())));
+                },
+            )
+            .add_systems(
+                Update,
+                (
+                    apply_loaded_map,
+                    report_map_error,
+                    apply_tile_changes.run_if(resource_exists::<Grid>),
+                    bake_walls,
+                    update_navigation.run_if(resource_exists_and_changed::<TileMap>),
+                ),
+            );
+    }
+}
+
+/// Event for changing a single tile of the loaded map at runtime (e.g., to open a door or to
+/// break a wall). Only the tile and the walls around it are spawned again.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SetTile {
+    pub position: UVec2,
+    pub tile: Tile,
+}
+
+/// Handle to the map currently played (without the overrides of its level).
+#[derive(Resource)]
+pub struct MapHandle(pub Handle<TileMap>);
+
+/// Handle to the level pack played.
+#[derive(Resource)]
+pub struct LevelPackHandle(pub Handle<LevelPack>);
+
+/// Index of the level of the pack currently played.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CurrentLevel(pub usize);
+
+pub type TileEntityQuery<'world, 'state> = Query<
+    'world,
+    'state,
+    Entity,
+    Or<(
+        With<WallTile>,
+        With<Coin>,
+        With<Teleporter>,
+        With<Door>,
+        With<SpecialTile>,
+    )>,
+>;
+
+/// Everything needed to start a level of the loaded pack.
+#[derive(SystemParam)]
+pub struct LevelStarter<'w, 's> {
+    commands: Commands<'w, 's>,
+    handle: Res<'w, LevelPackHandle>,
+    packs: Res<'w, Assets<LevelPack>>,
+    maps: Res<'w, Assets<TileMap>>,
+    ascii: Res<'w, AsciiSheet>,
+    rendering: Res<'w, WallRendering>,
+    tiles: TileEntityQuery<'w, 's>,
+}
+
+impl LevelStarter<'_, '_> {
+    /// The level pack played, if it is loaded.
+    pub fn pack(&self) -> Option<&LevelPack> {
+        self.packs.get(&self.handle.0)
+    }
+
+    /// Spawn the tiles of the level with the given index and insert its map (with the overrides
+    /// of the level applied) as a resource. Returns `false`, if there is no such level or its map
+    /// is not loaded.
+    pub fn start(&mut self, index: usize) -> bool {
+        let Some(level) = self.pack().and_then(|pack| pack.levels.get(index)).cloned() else {
+            return false;
+        };
+        let Some(map) = self.maps.get(&level.map) else {
+            return false;
+        };
+
+        let map = level.overrides.apply(map);
+        respawn_tiles(
+            &mut self.commands,
+            &map,
+            &self.ascii,
+            *self.rendering,
+            &self.tiles,
+        );
+        self.commands.insert_resource(map);
+        self.commands.insert_resource(MapHandle(level.map));
+        true
+    }
+}
+
+/// Start the current level, as soon as the pack and all its maps are loaded. If the pack or the
+/// map of the current level is modified while playing or editing (e.g., because its file
+/// changed), all tiles are despawned and spawned again.
+fn apply_loaded_map(
+    mut levels: LevelStarter,
+    mut pack_events: EventReader<AssetEvent<LevelPack>>,
+    mut map_events: EventReader<AssetEvent<TileMap>>,
+    level: Res<CurrentLevel>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let pack_id = levels.handle.0.id();
+    let pack_changed = pack_events.read().any(|event| {
+        matches!(*event, AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } if id == pack_id)
+    });
+
+    let map_id = levels
+        .pack()
+        .and_then(|pack| pack.levels.get(level.0))
+        .map(|level| level.map.id());
+    let map_changed = map_events
+        .read()
+        .any(|event| matches!(*event, AssetEvent::Modified { id } if Some(id) == map_id));
+
+    if !(pack_changed || map_changed) || !levels.start(level.0) {
+        return;
+    }
+
+    if matches!(state.get(), GameState::Loading | GameState::MapError) {
+        info!("loaded level {}", level.0 + 1);
+        next_state.set(GameState::Playing);
+    } else {
+        info!("reloaded level {}", level.0 + 1);
+    }
+}
+
+/// Tell the user why the pack or one of its maps could not be loaded. If we are already playing,
+/// we just keep the old map.
+fn report_map_error(
+    mut commands: Commands,
+    mut map_events: EventReader<AssetLoadFailedEvent<TileMap>>,
+    mut pack_events: EventReader<AssetLoadFailedEvent<LevelPack>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let errors = map_events
+        .read()
+        .map(|event| event.error.to_string())
+        .chain(pack_events.read().map(|event| event.error.to_string()))
+        .collect::<Vec<_>>();
+
+    for error in errors {
+        error!("{error}");
+
+        if *state.get() != GameState::Loading {
+            continue;
+        }
+
+        commands.spawn(TextBundle::from_section(
+            error,
+            TextStyle {
+                font_size: 20.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ));
+        next_state.set(GameState::MapError);
+    }
+}
+
+/// Despawn all tiles and spawn them again according to the given map.
+pub fn respawn_tiles(
+    commands: &mut Commands,
+    map: &TileMap,
+    ascii: &Res<AsciiSheet>,
+    rendering: WallRendering,
+    tiles: &TileEntityQuery,
+) {
+    for entity in tiles {
+        commands.entity(entity).despawn_recursive();
+    }
+    spawn_tiles(commands, map, ascii, rendering);
+}
+
+/// Spawn tiles depending on the loaded map and insert the grid for looking them up.
+pub fn spawn_tiles(
+    commands: &mut Commands,
+    map: &TileMap,
+    ascii: &Res<AsciiSheet>,
+    rendering: WallRendering,
+) {
+    let mut grid = Grid::from(map);
+    let wall_kinds = WallKind::classify(map);
+
+    for x in 0..map.width() {
+        for y in 0..map.height() {
+            spawn_tile(
+                commands,
+                ascii,
+                rendering,
+                map,
+                &wall_kinds,
+                &mut grid,
+                x,
+                y,
+            );
+        }
+    }
+
+    commands.insert_resource(grid);
+}
+
+/// Spawn the entity for a single tile (if it needs one) and remember it in the grid.
+#[allow(clippy::too_many_arguments)]
+fn spawn_tile(
+    commands: &mut Commands,
+    ascii: &Res<AsciiSheet>,
+    rendering: WallRendering,
+    map: &TileMap,
+    wall_kinds: &[Vec<Option<WallKind>>],
+    grid: &mut Grid,
+    x: usize,
+    y: usize,
+) {
+    let entity = match map.at(x as i32, y as i32) {
+        Some(Tile::Wall) => {
+            let kind = wall_kinds[x][y].unwrap_or(WallKind::Inner);
+            spawn_sprites_for_wall(commands, ascii, map, kind, rendering, x as i32, y as i32)
+        }
+        Some(Tile::Coin) => spawn_coin(commands, ascii, x, y),
+        Some(Tile::Teleporter(_)) => spawn_teleporter(commands, ascii, x, y),
+        Some(Tile::Door) => {
+            let horizontal = map.at(x as i32 - 1, y as i32) == Some(Tile::Wall)
+                || map.at(x as i32 + 1, y as i32) == Some(Tile::Wall);
+            spawn_door(commands, ascii, horizontal, x, y)
+        }
+        Some(tile @ (Tile::Slow | Tile::OneWay(_) | Tile::NoGhosts)) => {
+            spawn_special_tile(commands, ascii, tile, x, y)
+        }
+        _ => return,
+    };
+
+    grid.set_entity(x as i32, y as i32, entity);
+}
+
+/// Get the tiles, which have to be spawned again after the given tiles changed: the changed
+/// tiles, the walls and doors around them (so they connect to their new neighbours) and all walls
+/// whose kind changed, e.g., because they are connected to the border now.
+fn affected_tiles(
+    map: &TileMap,
+    changed: &[UVec2],
+    old_kinds: &[Vec<Option<WallKind>>],
+    new_kinds: &[Vec<Option<WallKind>>],
+) -> Vec<(i32, i32)> {
+    let around = changed
+        .iter()
+        .flat_map(|position| {
+            let (x, y) = (position.x as i32, position.y as i32);
+            (-1..=1).flat_map(move |dx| (-1..=1).map(move |dy| (x + dx, y + dy)))
+        })
+        .filter(|&(x, y)| {
+            changed.contains(&UVec2::new(x as u32, y as u32))
+                || matches!(map.at(x, y), Some(Tile::Wall | Tile::Door))
+        });
+    let reclassified = map.iter().map(|(position, _)| position).filter(|position| {
+        let (x, y) = (position.x as usize, position.y as usize);
+        let kind = new_kinds[x][y];
+        kind.is_some() && old_kinds.get(x).and_then(|column| column.get(y)) != Some(&kind)
+    });
+
+    let mut affected = around
+        .chain(reclassified.map(|position| (position.x as i32, position.y as i32)))
+        .collect::<Vec<_>>();
+    affected.sort_unstable();
+    affected.dedup();
+    affected
+}
+
+/// Change tiles of the map and spawn all tiles affected by the changes again (see
+/// [`affected_tiles`]).
+fn apply_tile_changes(
+    mut commands: Commands,
+    mut events: EventReader<SetTile>,
+    mut map: ResMut<TileMap>,
+    mut grid: ResMut<Grid>,
+    ascii: Res<AsciiSheet>,
+    rendering: Res<WallRendering>,
+) {
+    let changes = events
+        .read()
+        .filter(|change| {
+            // eaten coins are only missing in the grid, but not in the map
+            let UVec2 { x, y } = change.position;
+            let (x, y) = (x as i32, y as i32);
+            map.at(x, y).is_some()
+                && (map.at(x, y), grid.tile(x, y)) != (Some(change.tile), Some(change.tile))
+        })
+        .copied()
+        .collect::<Vec<_>>();
+    if changes.is_empty() {
+        return;
+    }
+
+    let old_kinds = WallKind::classify(&map);
+    for SetTile { position, tile } in &changes {
+        map.set(position.x as usize, position.y as usize, *tile);
+        grid.update_tile(&map, position.x as i32, position.y as i32);
+    }
+    let wall_kinds = WallKind::classify(&map);
+
+    let changed = changes
+        .iter()
+        .map(|change| change.position)
+        .collect::<Vec<_>>();
+    for (x, y) in affected_tiles(&map, &changed, &old_kinds, &wall_kinds) {
+        if let Some(entity) = grid.take_entity(x, y) {
+            commands.entity(entity).despawn_recursive();
         }
+        spawn_tile(
+            &mut commands,
+            &ascii,
+            *rendering,
+            &map,
+            &wall_kinds,
+            &mut grid,
+            x as usize,
+            y as usize,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::UVec2;
+
+    use super::{affected_tiles, Tile, TileMap, WallKind};
+
+    #[test]
+    fn test_affected_tiles() {
+        let mut map = "#########\n#.......#\n#.#####.#\n#-......#\n#@......#\n#########"
+            .parse::<TileMap>()
+            .unwrap();
+        let old_kinds = WallKind::classify(&map);
+
+        // connecting the inner block to the border turns all of its walls into outer walls
+        map.set(1, 3, Tile::Wall);
+        let new_kinds = WallKind::classify(&map);
+        let affected = affected_tiles(&map, &[UVec2::new(1, 3)], &old_kinds, &new_kinds);
+
+        assert!(affected.contains(&(1, 3)));
+        assert!(affected.contains(&(0, 4)));
+        assert!(affected.contains(&(1, 2)), "the door next to it is turned");
+        assert!(
+            affected.contains(&(6, 3)),
+            "the far end of the block is reclassified"
+        );
+        assert!(!affected.contains(&(8, 0)));
+        assert!(!affected.contains(&(3, 1)));
     }
 }
@@ -0,0 +1,421 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use bevy::prelude::*;
+
+use crate::entities::{GridPosition, MovementDirection};
+
+use super::{Tile, TileMap};
+
+/// Who is moving over the map, since only ghosts may pass doors and only pacman may pass tiles
+/// without ghosts.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Walker {
+    Pacman,
+    Ghost,
+}
+
+impl Walker {
+    /// Check, whether this walker may move onto the given tile.
+    pub fn can_enter(self, tile: Tile) -> bool {
+        match tile {
+            Tile::Wall => false,
+            Tile::Door => self == Walker::Ghost,
+            Tile::NoGhosts => self == Walker::Pacman,
+            _ => true,
+        }
+    }
+}
+
+/// All directions, in the order in which ties between equally short ways are broken.
+const DIRECTIONS: [MovementDirection; 4] = [
+    MovementDirection::Up,
+    MovementDirection::Left,
+    MovementDirection::Down,
+    MovementDirection::Right,
+];
+
+/// A single move from one tile to the tile (with the given index) an entity ends up on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Step {
+    direction: MovementDirection,
+    target: usize,
+}
+
+/// Number of steps needed between a single tile and every other tile of the map.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DistanceField {
+    width: usize,
+    distances: Vec<Option<u32>>,
+}
+
+impl DistanceField {
+    /// Get the number of steps for the given position. Returns `None`, if the position can not
+    /// be reached (or is outside of the map).
+    pub fn get(&self, position: UVec2) -> Option<u32> {
+        if position.x as usize >= self.width {
+            return None;
+        }
+        let index = position.y as usize * self.width + position.x as usize;
+        self.distances.get(index).copied().flatten()
+    }
+}
+
+/// The ways pacman and the ghosts can move over a map, following the same rules as the game
+/// loop: walls block everyone, doors block pacman, tiles without ghosts block the ghosts,
+/// one-way tiles block everyone moving against their arrow, the border can only be crossed in
+/// tunnels and arriving at a teleporter moves an entity to its partner.
+///
+/// Distance fields towards a target are cached, so many entities chasing the same target only
+/// compute it once. The navigation is built again whenever the map changes.
+#[derive(Resource, Debug)]
+pub struct Navigation {
+    width: usize,
+    height: usize,
+    /// Steps possible from every tile, for pacman and for the ghosts.
+    steps: [Vec<Vec<Step>>; 2],
+    /// Tiles from which every tile can be reached in one step, for pacman and for the ghosts.
+    predecessors: [Vec<Vec<usize>>; 2],
+    /// Positions where an entity can come out of a teleporter.
+    teleporters: Vec<UVec2>,
+    cache: Mutex<HashMap<(UVec2, Walker), Arc<DistanceField>>>,
+}
+
+impl From<&TileMap> for Navigation {
+    fn from(map: &TileMap) -> Self {
+        let width = map.width();
+        let height = map.height();
+        let walkers = [Walker::Pacman, Walker::Ghost];
+
+        // tiles are indexed row by row, starting at the bottom left
+        let steps = walkers.map(|walker| {
+            (0..width * height)
+                .map(|index| {
+                    let position = UVec2::new((index % width) as u32, (index / width) as u32);
+                    DIRECTIONS
+                        .into_iter()
+                        .filter_map(|direction| {
+                            let target = step(map, walker, position, direction)?;
+                            Some(Step {
+                                direction,
+                                target: target.y as usize * width + target.x as usize,
+                            })
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        });
+
+        let predecessors = steps.clone().map(|steps| {
+            let mut predecessors = vec![vec![]; width * height];
+            for (index, steps) in steps.iter().enumerate() {
+                for step in steps {
+                    predecessors[step.target].push(index);
+                }
+            }
+            predecessors
+        });
+
+        let teleporters = map
+            .iter()
+            .filter(|(_, tile)| matches!(tile, Tile::Teleporter(_)))
+            .map(|(position, _)| position)
+            .collect();
+
+        Self {
+            width,
+            height,
+            steps,
+            predecessors,
+            teleporters,
+            cache: Mutex::default(),
+        }
+    }
+}
+
+/// Determine where a walker ends up when leaving the position in the given direction.
+fn step(
+    map: &TileMap,
+    walker: Walker,
+    position: UVec2,
+    direction: MovementDirection,
+) -> Option<UVec2> {
+    let mut next = GridPosition::from(position) + direction;
+
+    if map.at(next.x, next.y).is_none() {
+        if map[position] != Tile::Tunnel {
+            return None;
+        }
+        next = GridPosition::new(
+            next.x.rem_euclid(map.width() as i32),
+            next.y.rem_euclid(map.height() as i32),
+        );
+    }
+
+    let next_tile = map.at(next.x, next.y)?;
+    if !walker.can_enter(next_tile)
+        || !map[position].allows(direction)
+        || !next_tile.allows(direction)
+    {
+        return None;
+    }
+
+    let next = UVec2::new(next.x as u32, next.y as u32);
+    Some(
+        map.teleport_target(next.x as i32, next.y as i32)
+            .unwrap_or(next),
+    )
+}
+
+impl Navigation {
+    fn index(&self, position: UVec2) -> Option<usize> {
+        let (x, y) = (position.x as usize, position.y as usize);
+        (x < self.width && y < self.height).then_some(y * self.width + x)
+    }
+
+    fn position(&self, index: usize) -> UVec2 {
+        UVec2::new((index % self.width) as u32, (index / self.width) as u32)
+    }
+
+    /// Breadth first search from a single tile along the given edges.
+    fn distance_field(&self, start: usize, edges: impl Fn(usize) -> Vec<usize>) -> DistanceField {
+        let mut distances = vec![None; self.width * self.height];
+        distances[start] = Some(0);
+        let mut queue = VecDeque::from([start]);
+
+        while let Some(index) = queue.pop_front() {
+            let distance = distances[index].unwrap_or(0) + 1;
+            for next in edges(index) {
+                if distances[next].is_none() {
+                    distances[next] = Some(distance);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        DistanceField {
+            width: self.width,
+            distances,
+        }
+    }
+
+    /// Number of steps the walker needs from the given position to every other tile.
+    pub fn distances_from(&self, start: UVec2, walker: Walker) -> Option<DistanceField> {
+        let start = self.index(start)?;
+        let steps = &self.steps[walker as usize];
+
+        Some(self.distance_field(start, |index| {
+            steps[index].iter().map(|step| step.target).collect()
+        }))
+    }
+
+    /// Number of steps the walker needs from every tile to the given target. The result is cached
+    /// until the map changes.
+    pub fn distances_to(&self, target: UVec2, walker: Walker) -> Option<Arc<DistanceField>> {
+        let index = self.index(target)?;
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+
+        let field = cache.entry((target, walker)).or_insert_with(|| {
+            let predecessors = &self.predecessors[walker as usize];
+            Arc::new(self.distance_field(index, |index| predecessors[index].clone()))
+        });
+        Some(field.clone())
+    }
+
+    /// Direction the walker has to go from its position to get closer to the target. Returns
+    /// `None`, if it already is at the target or can not reach it.
+    // not used by the game, until the ghosts move on their own
+    #[allow(dead_code)]
+    pub fn next_step(&self, from: UVec2, to: UVec2, walker: Walker) -> Option<MovementDirection> {
+        let index = self.index(from)?;
+        let distances = self.distances_to(to, walker)?;
+        if from == to {
+            return None;
+        }
+
+        self.steps[walker as usize][index]
+            .iter()
+            .filter_map(|step| {
+                let distance = distances.get(self.position(step.target))?;
+                Some((distance, step.direction))
+            })
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, direction)| direction)
+    }
+
+    /// Lower bound of the steps needed between two positions: wrapping around the border and
+    /// teleporting can only make ways shorter.
+    fn estimate(&self, from: UVec2, to: UVec2) -> u32 {
+        let (width, height) = (self.width as u32, self.height as u32);
+        let wrapped = |from: UVec2, to: UVec2| {
+            let dx = from.x.abs_diff(to.x);
+            let dy = from.y.abs_diff(to.y);
+            dx.min(width - dx) + dy.min(height - dy)
+        };
+
+        self.teleporters
+            .iter()
+            .map(|exit| wrapped(*exit, to) + 1)
+            .fold(wrapped(from, to), u32::min)
+    }
+
+    /// Find a shortest way (using A*) from one position to another, including both of them.
+    /// Returns `None`, if the target can not be reached.
+    pub fn path(&self, from: UVec2, to: UVec2, walker: Walker) -> Option<Vec<UVec2>> {
+        let start = self.index(from)?;
+        let goal = self.index(to)?;
+        let steps = &self.steps[walker as usize];
+
+        let mut costs = vec![u32::MAX; self.width * self.height];
+        let mut previous = vec![None; self.width * self.height];
+        let mut open = BinaryHeap::from([Reverse((self.estimate(from, to), start))]);
+        costs[start] = 0;
+
+        while let Some(Reverse((_, index))) = open.pop() {
+            if index == goal {
+                let mut path = vec![to];
+                let mut current = goal;
+                while let Some(before) = previous[current] {
+                    path.push(self.position(before));
+                    current = before;
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            let cost = costs[index] + 1;
+            for step in &steps[index] {
+                if cost < costs[step.target] {
+                    costs[step.target] = cost;
+                    previous[step.target] = Some(index);
+                    let estimate = cost + self.estimate(self.position(step.target), to);
+                    open.push(Reverse((estimate, step.target)));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Build the navigation for the current map, whenever it changed.
+pub fn update_navigation(mut commands: Commands, map: Res<TileMap>) {
+    commands.insert_resource(Navigation::from(&*map));
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::UVec2;
+
+    use crate::{
+        entities::MovementDirection,
+        map::{Navigation, TileMap, Walker},
+    };
+
+    fn navigation(map: &str) -> Navigation {
+        Navigation::from(&map.parse::<TileMap>().unwrap())
+    }
+
+    #[test]
+    fn test_distances() {
+        let navigation = navigation("######\n#@ #.#\n#    #\n######");
+        let from = navigation
+            .distances_from(UVec2::new(1, 2), Walker::Pacman)
+            .unwrap();
+        let to = navigation
+            .distances_to(UVec2::new(1, 2), Walker::Pacman)
+            .unwrap();
+
+        assert_eq!(from.get(UVec2::new(1, 2)), Some(0));
+        assert_eq!(from.get(UVec2::new(4, 2)), Some(5));
+        assert_eq!(from.get(UVec2::new(3, 2)), None);
+        assert_eq!(from.get(UVec2::new(6, 2)), None);
+        assert_eq!(to.get(UVec2::new(4, 2)), Some(5));
+    }
+
+    #[test]
+    fn test_doors_only_let_ghosts_pass() {
+        let navigation = navigation("#####\n#@-B#\n#####");
+
+        assert_eq!(
+            navigation.next_step(UVec2::new(1, 1), UVec2::new(3, 1), Walker::Pacman),
+            None
+        );
+        assert_eq!(
+            navigation.next_step(UVec2::new(3, 1), UVec2::new(1, 1), Walker::Ghost),
+            Some(MovementDirection::Left)
+        );
+    }
+
+    #[test]
+    fn test_one_way_and_no_ghosts_tiles() {
+        let one_way = navigation("######\n#@ > #\n######");
+
+        assert_eq!(
+            one_way.path(UVec2::new(1, 1), UVec2::new(4, 1), Walker::Pacman),
+            Some(vec![
+                UVec2::new(1, 1),
+                UVec2::new(2, 1),
+                UVec2::new(3, 1),
+                UVec2::new(4, 1)
+            ])
+        );
+        assert_eq!(
+            one_way.path(UVec2::new(4, 1), UVec2::new(1, 1), Walker::Pacman),
+            None
+        );
+
+        let no_ghosts = navigation("#####\n#@+B#\n#####");
+        assert_eq!(
+            no_ghosts.next_step(UVec2::new(1, 1), UVec2::new(3, 1), Walker::Pacman),
+            Some(MovementDirection::Right)
+        );
+        assert_eq!(
+            no_ghosts.next_step(UVec2::new(3, 1), UVec2::new(1, 1), Walker::Ghost),
+            None
+        );
+    }
+
+    #[test]
+    fn test_tunnels_and_teleporters() {
+        let tunnel = navigation("#######\n=@ #  =\n#######");
+
+        assert_eq!(
+            tunnel.next_step(UVec2::new(1, 1), UVec2::new(4, 1), Walker::Pacman),
+            Some(MovementDirection::Left)
+        );
+        assert_eq!(
+            tunnel.path(UVec2::new(1, 1), UVec2::new(4, 1), Walker::Pacman),
+            Some(vec![
+                UVec2::new(1, 1),
+                UVec2::new(0, 1),
+                UVec2::new(6, 1),
+                UVec2::new(5, 1),
+                UVec2::new(4, 1)
+            ])
+        );
+
+        let teleporter = navigation("#######\n#@1#1 #\n#######");
+        assert_eq!(
+            teleporter.path(UVec2::new(1, 1), UVec2::new(5, 1), Walker::Pacman),
+            Some(vec![UVec2::new(1, 1), UVec2::new(4, 1), UVec2::new(5, 1)])
+        );
+    }
+
+    #[test]
+    fn test_path_is_shortest() {
+        let navigation = navigation("#######\n#@    #\n# ### #\n#     #\n#######");
+        let path = navigation
+            .path(UVec2::new(1, 3), UVec2::new(5, 1), Walker::Pacman)
+            .unwrap();
+
+        assert_eq!(path.len(), 7);
+        assert_eq!(
+            navigation.path(UVec2::new(1, 3), UVec2::new(0, 0), Walker::Pacman),
+            None
+        );
+    }
+}
@@ -0,0 +1,322 @@
+use std::{path::Path, str::FromStr};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+};
+
+use super::{
+    MapMeta, Marker, PackError, ParseMapErrorKind, ParsePackError, ParsePackErrorKind, TileMap,
+};
+
+/// What happens after the last level of a pack is cleared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AfterLastLevel {
+    /// The game is won and a victory screen is shown.
+    #[default]
+    Victory,
+    /// The game continues with the first level.
+    Wrap,
+}
+
+/// Changes applied to a map, when it is played as a level of a pack.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LevelOverrides {
+    /// Header values replacing the ones of the map (e.g., `tick_speed` or `wall_color`).
+    pub meta: MapMeta,
+    /// Where the bonus fruit appears instead.
+    pub fruit: Option<UVec2>,
+}
+
+impl LevelOverrides {
+    /// Set the value for a key, which is either `fruit` (given as `x,y`) or a key of the map
+    /// header.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), ParsePackErrorKind> {
+        if key == "fruit" {
+            let position = value
+                .split_once(',')
+                .and_then(|(x, y)| Some(UVec2::new(x.parse().ok()?, y.parse().ok()?)))
+                .ok_or_else(|| ParsePackErrorKind::InvalidValue {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                })?;
+            self.fruit = Some(position);
+            return Ok(());
+        }
+
+        self.meta.set(key, value).map_err(|kind| match kind {
+            ParseMapErrorKind::InvalidHeaderValue { key, value } => {
+                ParsePackErrorKind::InvalidValue { key, value }
+            }
+            _ => ParsePackErrorKind::UnknownKey(key.to_string()),
+        })
+    }
+
+    /// Create a copy of the map with these overrides applied. A fruit outside of the map is
+    /// ignored.
+    pub fn apply(&self, map: &TileMap) -> TileMap {
+        let mut map = map.clone();
+        let meta = map.meta_mut();
+
+        if let Some(name) = &self.meta.name {
+            meta.name = Some(name.clone());
+        }
+        if let Some(author) = &self.meta.author {
+            meta.author = Some(author.clone());
+        }
+        if let Some(wall_color) = self.meta.wall_color {
+            meta.wall_color = Some(wall_color);
+        }
+        if let Some(tick_speed) = self.meta.tick_speed {
+            meta.tick_speed = Some(tick_speed);
+        }
+
+        if let Some(fruit) = self.fruit {
+            if map.at(fruit.x as i32, fruit.y as i32).is_some() {
+                map.spawns_mut().place(Marker::Fruit, fruit);
+            }
+        }
+
+        map
+    }
+}
+
+/// A level as listed in a pack file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelFile {
+    /// Path of the map, relative to the pack file.
+    pub path: String,
+    pub overrides: LevelOverrides,
+}
+
+/// The text representation of a level pack, e.g.:
+///
+/// ```text
+/// name: Lighthouse
+/// after_last: wrap
+///
+/// lighthouse.txt
+/// lighthouse.txt tick_speed=3 wall_color=#ff0080 fruit=6,5
+/// ```
+///
+/// The optional header consists of `key: value` lines, followed by one level per line: the path
+/// of its map and any number of `key=value` overrides.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LevelPackFile {
+    pub name: Option<String>,
+    pub after_last: AfterLastLevel,
+    pub levels: Vec<LevelFile>,
+}
+
+impl FromStr for LevelPackFile {
+    type Err = ParsePackError;
+
+    fn from_str(content: &str) -> Result<Self, Self::Err> {
+        let mut pack = LevelPackFile::default();
+
+        for (index, line) in content.lines().enumerate() {
+            let error = |kind| ParsePackError::new(index + 1, kind);
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            // ':' is not used by levels, so it only occurs in the header
+            if let (true, Some((key, value))) = (pack.levels.is_empty(), line.split_once(':')) {
+                let (key, value) = (key.trim(), value.trim());
+                match key {
+                    "name" => pack.name = Some(value.to_string()),
+                    "after_last" => {
+                        pack.after_last = match value {
+                            "victory" => AfterLastLevel::Victory,
+                            "wrap" => AfterLastLevel::Wrap,
+                            _ => {
+                                return Err(error(ParsePackErrorKind::InvalidValue {
+                                    key: key.to_string(),
+                                    value: value.to_string(),
+                                }))
+                            }
+                        }
+                    }
+                    _ => return Err(error(ParsePackErrorKind::UnknownKey(key.to_string()))),
+                }
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            let path = parts.next().unwrap_or_default().to_string();
+            let mut overrides = LevelOverrides::default();
+
+            for part in parts {
+                let Some((key, value)) = part.split_once('=') else {
+                    return Err(error(ParsePackErrorKind::MalformedOverride(
+                        part.to_string(),
+                    )));
+                };
+                overrides.set(key, value).map_err(error)?;
+            }
+
+            pack.levels.push(LevelFile { path, overrides });
+        }
+
+        if pack.levels.is_empty() {
+            return Err(ParsePackError::new(
+                content.lines().count().max(1),
+                ParsePackErrorKind::NoLevels,
+            ));
+        }
+
+        Ok(pack)
+    }
+}
+
+/// A level of a loaded pack.
+#[derive(Debug, Clone)]
+pub struct Level {
+    pub map: Handle<TileMap>,
+    pub overrides: LevelOverrides,
+}
+
+/// An ordered list of maps, which are played one after another.
+#[derive(Asset, TypePath, Debug, Clone)]
+pub struct LevelPack {
+    pub name: Option<String>,
+    pub after_last: AfterLastLevel,
+    pub levels: Vec<Level>,
+}
+
+/// Loader for level packs, which also loads all maps of the pack.
+#[derive(Default)]
+pub struct LevelPackLoader;
+
+impl AssetLoader for LevelPackLoader {
+    type Asset = LevelPack;
+    type Settings = ();
+    type Error = PackError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut content = String::new();
+        reader.read_to_string(&mut content).await?;
+        let file = content.parse::<LevelPackFile>()?;
+
+        let directory = load_context
+            .path()
+            .parent()
+            .unwrap_or(Path::new(""))
+            .to_path_buf();
+        let levels = file
+            .levels
+            .into_iter()
+            .map(|level| Level {
+                map: load_context.load(directory.join(level.path)),
+                overrides: level.overrides,
+            })
+            .collect();
+
+        Ok(LevelPack {
+            name: file.name,
+            after_last: file.after_last,
+            levels,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["pack"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::UVec2;
+
+    use crate::map::{AfterLastLevel, LevelPackFile, ParsePackError, ParsePackErrorKind, TileMap};
+
+    #[test]
+    fn test_parse_pack() {
+        let pack = "name: Test\nafter_last: wrap\n\none.txt\ntwo.txt tick_speed=3 fruit=1,2\n"
+            .parse::<LevelPackFile>()
+            .unwrap();
+
+        assert_eq!(pack.name.as_deref(), Some("Test"));
+        assert_eq!(pack.after_last, AfterLastLevel::Wrap);
+        assert_eq!(pack.levels.len(), 2);
+        assert_eq!(pack.levels[0].path, "one.txt");
+        assert_eq!(pack.levels[1].path, "two.txt");
+        assert_eq!(pack.levels[1].overrides.meta.tick_speed, Some(3.0));
+        assert_eq!(pack.levels[1].overrides.fruit, Some(UVec2::new(1, 2)));
+    }
+
+    #[test]
+    fn test_parse_invalid_pack() {
+        assert_eq!(
+            "name: Empty\n".parse::<LevelPackFile>().err(),
+            Some(ParsePackError::new(1, ParsePackErrorKind::NoLevels))
+        );
+        assert_eq!(
+            "one.txt\ntwo.txt speed=3".parse::<LevelPackFile>().err(),
+            Some(ParsePackError::new(
+                2,
+                ParsePackErrorKind::UnknownKey("speed".into())
+            ))
+        );
+        assert_eq!(
+            "one.txt fruit=1".parse::<LevelPackFile>().err(),
+            Some(ParsePackError::new(
+                1,
+                ParsePackErrorKind::InvalidValue {
+                    key: "fruit".into(),
+                    value: "1".into()
+                }
+            ))
+        );
+        assert_eq!(
+            "after_last: never\none.txt".parse::<LevelPackFile>().err(),
+            Some(ParsePackError::new(
+                1,
+                ParsePackErrorKind::InvalidValue {
+                    key: "after_last".into(),
+                    value: "never".into()
+                }
+            ))
+        );
+        assert_eq!(
+            "one.txt fast".parse::<LevelPackFile>().err(),
+            Some(ParsePackError::new(
+                1,
+                ParsePackErrorKind::MalformedOverride("fast".into())
+            ))
+        );
+    }
+
+    #[test]
+    fn test_apply_overrides() {
+        let map = "tick_speed: 2\n#####\n#@.F#\n#####"
+            .parse::<TileMap>()
+            .unwrap();
+        let pack = "map.txt tick_speed=4 fruit=2,1\nmap.txt fruit=9,9"
+            .parse::<LevelPackFile>()
+            .unwrap();
+
+        let first = pack.levels[0].overrides.apply(&map);
+        assert_eq!(first.meta().tick_speed, Some(4.0));
+        assert_eq!(first.spawns().fruit, Some(UVec2::new(2, 1)));
+
+        let second = pack.levels[1].overrides.apply(&map);
+        assert_eq!(second, map);
+    }
+
+    #[test]
+    fn test_parse_stock_packs() {
+        for content in [
+            include_str!("../../assets/lighthouse.pack"),
+            include_str!("../../assets/desktop.pack"),
+        ] {
+            content.parse::<LevelPackFile>().unwrap();
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use bevy::prelude::*;
+
+use crate::entities::GhostType;
+
+/// Markers within a map file, which do not represent a tile on their own. The tile beneath a
+/// marker is always empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Marker {
+    Pacman,
+    Ghost(GhostType),
+    Fruit,
+    GhostHouse,
+}
+
+impl Marker {
+    /// Get the marker represented by the given character in a map file.
+    /// Returns `None`, if the character does not represent any marker.
+    pub fn from_char(character: char) -> Option<Self> {
+        match character {
+            '@' => Some(Self::Pacman),
+            'B' => Some(Self::Ghost(GhostType::Blinky)),
+            'I' => Some(Self::Ghost(GhostType::Inky)),
+            'P' => Some(Self::Ghost(GhostType::Pinky)),
+            'C' => Some(Self::Ghost(GhostType::Clyde)),
+            'F' => Some(Self::Fruit),
+            'H' => Some(Self::GhostHouse),
+            _ => None,
+        }
+    }
+
+    /// Get the character representing this marker in a map file.
+    pub fn to_char(self) -> char {
+        match self {
+            Self::Pacman => '@',
+            Self::Ghost(GhostType::Blinky) => 'B',
+            Self::Ghost(GhostType::Inky) => 'I',
+            Self::Ghost(GhostType::Pinky) => 'P',
+            Self::Ghost(GhostType::Clyde) => 'C',
+            Self::Fruit => 'F',
+            Self::GhostHouse => 'H',
+        }
+    }
+}
+
+/// Positions of all markers on a map.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpawnPoints {
+    /// Where pacman starts.
+    pub pacman: UVec2,
+    /// Where each ghost starts. Every ghost type appears at most once.
+    pub ghosts: Vec<(GhostType, UVec2)>,
+    /// Where the bonus fruit appears, if the map has one.
+    pub fruit: Option<UVec2>,
+    /// All tiles belonging to the ghost house.
+    pub ghost_house: Vec<UVec2>,
+}
+
+impl SpawnPoints {
+    /// Place a marker at the given position, replacing other markers at this position. Markers
+    /// which may only occur once are moved to the new position.
+    pub fn place(&mut self, marker: Marker, position: UVec2) {
+        self.remove_at(position);
+
+        match marker {
+            Marker::Pacman => self.pacman = position,
+            Marker::Ghost(ghost) => {
+                self.ghosts.retain(|(other, _)| *other != ghost);
+                self.ghosts.push((ghost, position));
+            }
+            Marker::Fruit => self.fruit = Some(position),
+            Marker::GhostHouse => self.ghost_house.push(position),
+        }
+    }
+
+    /// Remove all markers at the given position. The spawn of pacman is required, so it can only
+    /// be moved, but not removed.
+    pub fn remove_at(&mut self, position: UVec2) {
+        self.ghosts.retain(|(_, other)| *other != position);
+        self.ghost_house.retain(|other| *other != position);
+        if self.fruit == Some(position) {
+            self.fruit = None;
+        }
+    }
+
+    /// Get the marker at the given position, if there is one.
+    pub fn marker_at(&self, position: UVec2) -> Option<Marker> {
+        if self.pacman == position {
+            return Some(Marker::Pacman);
+        }
+        if let Some((ghost, _)) = self.ghosts.iter().find(|(_, pos)| *pos == position) {
+            return Some(Marker::Ghost(*ghost));
+        }
+        if self.fruit == Some(position) {
+            return Some(Marker::Fruit);
+        }
+        if self.ghost_house.contains(&position) {
+            return Some(Marker::GhostHouse);
+        }
+        None
+    }
+}
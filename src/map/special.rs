@@ -0,0 +1,108 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use bevy::prelude::*;
+
+use crate::{
+    ascii::{AsciiSheet, SpriteIndices},
+    entities::{GridPosition, MovementDirection},
+    lighthouse::{LighthouseBundle, LighthouseColor, LighthousePosition},
+};
+
+use super::Tile;
+
+/// Component for tiles changing how entities move over them: slow tiles, one-way tiles and tiles
+/// without ghosts.
+#[derive(Component)]
+pub struct SpecialTile;
+
+/// Sprite, rotation and colors (on the screen and on the lighthouse) of a special tile. Returns
+/// `None` for all other tiles.
+fn appearance(tile: Tile) -> Option<(SpriteIndices, f32, Color, LighthouseColor)> {
+    match tile {
+        Tile::Slow => Some((
+            SpriteIndices::SlowTile,
+            0.0,
+            Color::srgb(0.6, 0.4, 0.2),
+            LighthouseColor::Inline(153, 102, 51),
+        )),
+        // the arrow of the sprite points upwards
+        Tile::OneWay(direction) => {
+            let rotation = match direction {
+                MovementDirection::Up => 0.0,
+                MovementDirection::Left => FRAC_PI_2,
+                MovementDirection::Down => PI,
+                MovementDirection::Right => -FRAC_PI_2,
+            };
+            Some((
+                SpriteIndices::OneWayArrow,
+                rotation,
+                Color::srgb(0.3, 0.9, 0.3),
+                LighthouseColor::Inline(76, 230, 76),
+            ))
+        }
+        Tile::NoGhosts => Some((
+            SpriteIndices::NoGhostsTile,
+            0.0,
+            Color::srgb(1.0, 0.5, 0.2),
+            LighthouseColor::Inline(255, 128, 51),
+        )),
+        _ => None,
+    }
+}
+
+/// Spawn a special tile at the given location and return its entity. Other tiles are spawned
+/// without a sprite.
+pub fn spawn_special_tile(
+    commands: &mut Commands,
+    ascii: &Res<AsciiSheet>,
+    tile: Tile,
+    x: usize,
+    y: usize,
+) -> Entity {
+    let mut entity = commands.spawn(SpecialTile);
+    entity
+        .insert(GridPosition::new(x as i32, y as i32))
+        .insert(SpatialBundle {
+            // x and y are derived from the grid position
+            transform: Transform::from_xyz(0.0, 0.0, 1.0),
+            ..default()
+        });
+
+    let Some((sprite_index, rotation, color, lighthouse_color)) = appearance(tile) else {
+        return entity.id();
+    };
+
+    let sprite = Sprite {
+        custom_size: Some(Vec2::splat(1.0)),
+        color,
+        ..default()
+    };
+
+    let atlas = TextureAtlas {
+        index: ascii.index(sprite_index),
+        layout: ascii.layout.clone(),
+    };
+
+    entity
+        .insert(LighthouseBundle {
+            position: LighthousePosition { x: 0, y: 0, z: 1 },
+            color: lighthouse_color,
+        })
+        .with_children(|parent| {
+            // the sprite is rotated around the centre of the tile
+            parent.spawn((
+                SpriteBundle {
+                    sprite,
+                    transform: Transform {
+                        translation: Vec3::new(0.5, 0.5, 0.0),
+                        rotation: Quat::from_rotation_z(rotation),
+                        ..default()
+                    },
+                    texture: ascii.image.clone(),
+                    ..default()
+                },
+                atlas,
+            ));
+        })
+        .id()
+}
@@ -0,0 +1,53 @@
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::{
+    ascii::{AsciiSheet, SpriteIndices},
+    entities::GridPosition,
+    lighthouse::{LighthouseBundle, LighthouseColor, LighthousePosition},
+};
+
+#[derive(Component)]
+pub struct Teleporter;
+
+/// Spawn a teleporter at the given location and return its entity
+pub fn spawn_teleporter(
+    commands: &mut Commands,
+    ascii: &Res<AsciiSheet>,
+    x: usize,
+    y: usize,
+) -> Entity {
+    let sprite = Sprite {
+        custom_size: Some(Vec2::splat(1.0)),
+        anchor: Anchor::BottomLeft,
+        color: Color::srgb(0.4, 1.0, 0.8),
+        ..default()
+    };
+
+    let atlas = TextureAtlas {
+        index: ascii.index(SpriteIndices::LargeCoin),
+        layout: ascii.layout.clone(),
+    };
+
+    commands
+        .spawn(Teleporter)
+        .insert(GridPosition::new(x as i32, y as i32))
+        .insert((
+            SpriteBundle {
+                sprite,
+                transform: Transform {
+                    // x and y are derived from the grid position
+                    translation: Vec3::new(0.0, 0.0, 1.0),
+                    scale: Vec3::new(1.0, 1.0, 0.0),
+                    ..default()
+                },
+                texture: ascii.image.clone(),
+                ..default()
+            },
+            atlas,
+        ))
+        .insert(LighthouseBundle {
+            position: LighthousePosition { x: 0, y: 0, z: 1 },
+            color: LighthouseColor::Inline(102, 255, 204),
+        })
+        .id()
+}
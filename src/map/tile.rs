@@ -1,22 +1,78 @@
 use bevy::prelude::*;
 
+use crate::entities::MovementDirection;
+
 /// A tile within the game.
-#[derive(Component, Debug, Clone, Copy)]
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Tile {
     Empty,
     Wall,
     Coin,
+    /// Entities leaving the map through a tunnel at its border come out at the opposite edge.
+    /// Ghosts are slowed down inside of tunnels.
+    Tunnel,
+    /// Entities arriving at a teleporter are moved to the other teleporter with the same number.
+    /// Every number has to be used by exactly two teleporters.
+    Teleporter(u8),
+    /// Only ghosts may pass doors, e.g., to leave the ghost house. They are the tiles forbidden
+    /// for pacman.
+    Door,
+    /// Pacman and the ghosts move at half of their speed on slow tiles.
+    Slow,
+    /// One-way tiles can only be passed in the direction of their arrow, so entities may neither
+    /// enter nor leave them against it.
+    OneWay(MovementDirection),
+    /// Tiles forbidden for ghosts, which only pacman may pass.
+    NoGhosts,
 }
 
 impl Tile {
-    pub fn from_char(character: char) -> Self {
+    /// Get the tile represented by the given character in a map file.
+    /// Returns `None`, if the character does not represent any tile.
+    pub fn from_char(character: char) -> Option<Self> {
         match character {
-            ' ' => Self::Empty,
-            '#' => Self::Wall,
-            '.' => Self::Coin,
-            _ => {
-                unimplemented!("Tile::from_char('{character}') not implemented yet!")
-            }
+            ' ' => Some(Self::Empty),
+            '#' => Some(Self::Wall),
+            '.' => Some(Self::Coin),
+            '=' => Some(Self::Tunnel),
+            '-' => Some(Self::Door),
+            '~' => Some(Self::Slow),
+            '^' => Some(Self::OneWay(MovementDirection::Up)),
+            '>' => Some(Self::OneWay(MovementDirection::Right)),
+            'v' => Some(Self::OneWay(MovementDirection::Down)),
+            '<' => Some(Self::OneWay(MovementDirection::Left)),
+            '+' => Some(Self::NoGhosts),
+            '0'..='9' => character
+                .to_digit(10)
+                .map(|digit| Self::Teleporter(digit as u8)),
+            _ => None,
+        }
+    }
+
+    /// Get the character representing this tile in a map file.
+    pub fn to_char(self) -> char {
+        match self {
+            Self::Empty => ' ',
+            Self::Wall => '#',
+            Self::Coin => '.',
+            Self::Tunnel => '=',
+            Self::Teleporter(number) => char::from_digit(number as u32, 10).unwrap_or('0'),
+            Self::Door => '-',
+            Self::Slow => '~',
+            Self::OneWay(MovementDirection::Up) => '^',
+            Self::OneWay(MovementDirection::Right) => '>',
+            Self::OneWay(MovementDirection::Down) => 'v',
+            Self::OneWay(MovementDirection::Left) => '<',
+            Self::NoGhosts => '+',
+        }
+    }
+
+    /// Check, whether entities may move in the given direction while entering or leaving this
+    /// tile, which is only forbidden against the arrow of one-way tiles.
+    pub fn allows(self, direction: MovementDirection) -> bool {
+        match self {
+            Self::OneWay(arrow) => direction != arrow.opposite(),
+            _ => true,
         }
     }
 }
@@ -0,0 +1,660 @@
+use std::{collections::HashMap, path::Path, str::FromStr};
+
+use bevy::asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext};
+use serde::Deserialize;
+use xml::reader::{EventReader, XmlEvent};
+
+use crate::entities::GhostType;
+
+use super::{Marker, Tile, TileMap, TiledError};
+
+/// Bits of a global tile id, which store whether the tile is flipped or rotated.
+const FLIP_FLAGS: u32 = 0xf000_0000;
+
+/// Name of the tileset property, which tells which tile (or marker) of our format a tile of
+/// Tiled represents.
+const TILE_PROPERTY: &str = "tile";
+
+/// Extensions of the map files of Tiled, which are imported.
+pub const TILED_EXTENSIONS: &[&str] = &["tmx", "tmj"];
+
+/// An object of an object layer, as a rectangle in pixels starting at the top left of the map.
+#[derive(Debug, Clone, PartialEq)]
+struct TiledObject {
+    kind: String,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+/// A custom property of a Tiled map.
+#[derive(Debug)]
+struct TiledProperty {
+    name: String,
+    value: String,
+    /// Whether the property has the type `color`, whose values are written as `#aarrggbb`.
+    is_color: bool,
+}
+
+/// The parts of a Tiled map we need, independent of the format of the file.
+#[derive(Debug, Default)]
+struct TiledMap {
+    width: usize,
+    height: usize,
+    tile_width: f32,
+    tile_height: f32,
+    /// Custom properties of the map, which are used as the header of the map.
+    properties: Vec<TiledProperty>,
+    /// Value of the `tile` property of every tile of the tilesets, by global tile id.
+    tiles: HashMap<u32, String>,
+    /// Global tile ids of the first tile layer, row by row starting at the top.
+    layer: Option<Vec<u32>>,
+    objects: Vec<TiledObject>,
+}
+
+impl TiledMap {
+    /// Character of our map format for the given global tile id.
+    fn tile_char(&self, gid: u32) -> Result<char, TiledError> {
+        let gid = gid & !FLIP_FLAGS;
+        if gid == 0 {
+            return Ok(Tile::Empty.to_char());
+        }
+
+        let mut characters = self
+            .tiles
+            .get(&gid)
+            .into_iter()
+            .flat_map(|tile| tile.chars());
+        match (characters.next(), characters.next()) {
+            (Some(character), None)
+                if Tile::from_char(character).is_some()
+                    || Marker::from_char(character).is_some() =>
+            {
+                Ok(character)
+            }
+            _ => Err(TiledError::UnknownTile(gid)),
+        }
+    }
+
+    /// Convert the map into our format. Objects are placed as markers onto the tiles they cover
+    /// and replace these tiles.
+    fn into_tile_map(self) -> Result<TileMap, TiledError> {
+        let Some(layer) = &self.layer else {
+            return Err(TiledError::Malformed(
+                "map does not contain a tile layer".to_string(),
+            ));
+        };
+        if layer.len() != self.width * self.height {
+            return Err(TiledError::Malformed(format!(
+                "expected {} tiles in the tile layer, found {}",
+                self.width * self.height,
+                layer.len()
+            )));
+        }
+
+        let mut rows = layer
+            .chunks(self.width.max(1))
+            .map(|row| row.iter().map(|gid| self.tile_char(*gid)).collect())
+            .collect::<Result<Vec<Vec<char>>, _>>()?;
+
+        // the ghost house is placed first, so the ghosts inside of it are kept
+        let mut objects = self
+            .objects
+            .iter()
+            .map(|object| Ok((marker_for(&object.kind)?, object)))
+            .collect::<Result<Vec<_>, TiledError>>()?;
+        objects.sort_by_key(|(marker, _)| *marker != Marker::GhostHouse);
+
+        for (marker, object) in objects {
+            let outside = || TiledError::ObjectOutsideMap(object.kind.clone());
+            let cells = |start: f32, size: f32, tile_size: f32| {
+                if marker == Marker::GhostHouse && size > 0.0 {
+                    let first = (start / tile_size).floor() as i64;
+                    let last = ((start + size) / tile_size).ceil() as i64;
+                    first..last
+                } else {
+                    let center = ((start + size / 2.0) / tile_size).floor() as i64;
+                    center..center + 1
+                }
+            };
+
+            for row in cells(object.y, object.height, self.tile_height) {
+                for column in cells(object.x, object.width, self.tile_width) {
+                    let cell = usize::try_from(row)
+                        .ok()
+                        .zip(usize::try_from(column).ok())
+                        .and_then(|(row, column)| rows.get_mut(row)?.get_mut(column))
+                        .ok_or_else(outside)?;
+                    *cell = marker.to_char();
+                }
+            }
+        }
+
+        let text = rows
+            .into_iter()
+            .map(|row| row.into_iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut map = text.parse::<TileMap>()?;
+
+        for property in &self.properties {
+            // our colors have no alpha channel
+            let value = match property.value.strip_prefix('#') {
+                Some(color) if property.is_color && color.len() == 8 => {
+                    format!("#{}", &color[2..])
+                }
+                _ => property.value.clone(),
+            };
+            map.meta_mut()
+                .set(&property.name, &value)
+                .map_err(TiledError::Property)?;
+        }
+
+        Ok(map)
+    }
+}
+
+/// The marker an object of the given type (or name) stands for.
+fn marker_for(kind: &str) -> Result<Marker, TiledError> {
+    match kind.to_lowercase().as_str() {
+        "pacman" => Ok(Marker::Pacman),
+        "blinky" => Ok(Marker::Ghost(GhostType::Blinky)),
+        "inky" => Ok(Marker::Ghost(GhostType::Inky)),
+        "pinky" => Ok(Marker::Ghost(GhostType::Pinky)),
+        "clyde" => Ok(Marker::Ghost(GhostType::Clyde)),
+        "fruit" => Ok(Marker::Fruit),
+        "ghost_house" => Ok(Marker::GhostHouse),
+        _ => Err(TiledError::UnknownObject(kind.to_string())),
+    }
+}
+
+/// Import a map saved by Tiled in its XML format (`.tmx`).
+///
+/// Only orthogonal, finite maps with embedded tilesets are supported. The first tile layer has
+/// to be stored as CSV (or as single `<tile>` elements). Every tile used needs a `tile` property
+/// with the character of the tile (or marker) in our map format. Objects of all object layers are
+/// placed as markers, using their type or (if it is empty) their name: `pacman`, `blinky`,
+/// `inky`, `pinky`, `clyde`, `fruit` or `ghost_house`. Custom properties of the map are used as
+/// its header.
+///
+/// Problems with the converted map are reported like problems of a map file, counting the rows
+/// and columns of the tile layer from the top left.
+pub fn import_tmx(content: &str) -> Result<TileMap, TiledError> {
+    let mut map = TiledMap::default();
+    let mut elements: Vec<String> = vec![];
+    let mut tileset_first_gid = 0;
+    let mut tile_id = None;
+    let mut layers = 0;
+
+    for event in EventReader::from_str(content) {
+        match event? {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } => {
+                let element = name.local_name;
+                let attribute = |key: &str| {
+                    attributes
+                        .iter()
+                        .find(|attribute| attribute.name.local_name == key)
+                        .map(|attribute| attribute.value.as_str())
+                };
+                let number = |key: &str| parse_attribute::<f32>(&element, key, attribute(key));
+                let parent = elements.last().map(String::as_str);
+                let first_layer = layers == 1 && elements.iter().any(|name| name == "layer");
+
+                match (parent, element.as_str()) {
+                    (None, "map") => {
+                        if attribute("orientation").is_some_and(|value| value != "orthogonal") {
+                            return Err(TiledError::Unsupported("non-orthogonal maps".into()));
+                        }
+                        if attribute("infinite") == Some("1") {
+                            return Err(TiledError::Unsupported("infinite maps".into()));
+                        }
+                        map.width = parse_attribute(&element, "width", attribute("width"))?;
+                        map.height = parse_attribute(&element, "height", attribute("height"))?;
+                        map.tile_width = number("tilewidth")?;
+                        map.tile_height = number("tileheight")?;
+                    }
+                    (Some("map"), "tileset") => {
+                        if attribute("source").is_some() {
+                            return Err(TiledError::Unsupported("external tilesets".into()));
+                        }
+                        tileset_first_gid =
+                            parse_attribute(&element, "firstgid", attribute("firstgid"))?;
+                    }
+                    (Some("tileset"), "tile") => {
+                        tile_id = Some(parse_attribute::<u32>(&element, "id", attribute("id"))?);
+                    }
+                    (Some("properties"), "property") => {
+                        let name = attribute("name").unwrap_or_default().to_string();
+                        let value = attribute("value").unwrap_or_default().to_string();
+                        match elements.iter().rev().nth(1).map(String::as_str) {
+                            Some("map") => map.properties.push(TiledProperty {
+                                name,
+                                value,
+                                is_color: attribute("type") == Some("color"),
+                            }),
+                            Some("tile") if name == TILE_PROPERTY => {
+                                if let Some(id) = tile_id {
+                                    map.tiles.insert(tileset_first_gid + id, value);
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                    (Some("map"), "layer") => {
+                        layers += 1;
+                        if layers == 1 {
+                            map.layer = Some(vec![]);
+                        }
+                    }
+                    (Some("layer"), "data") if first_layer => {
+                        if attribute("compression").is_some() {
+                            return Err(TiledError::Unsupported("compressed tile layers".into()));
+                        }
+                        if attribute("encoding").is_some_and(|encoding| encoding != "csv") {
+                            return Err(TiledError::Unsupported(
+                                "base64 encoded tile layers".into(),
+                            ));
+                        }
+                    }
+                    (Some("data"), "chunk") => {
+                        return Err(TiledError::Unsupported("infinite maps".into()));
+                    }
+                    (Some("data"), "tile") if first_layer => {
+                        let gid = match attribute("gid") {
+                            Some(gid) => parse_attribute(&element, "gid", Some(gid))?,
+                            None => 0,
+                        };
+                        map.layer.get_or_insert_with(Vec::new).push(gid);
+                    }
+                    (Some("objectgroup"), "object") => {
+                        let mut object = TiledObject {
+                            kind: attribute("type")
+                                .or(attribute("class"))
+                                .filter(|kind| !kind.is_empty())
+                                .or(attribute("name"))
+                                .unwrap_or_default()
+                                .to_string(),
+                            x: number("x")?,
+                            y: number("y")?,
+                            width: attribute("width").map_or(Ok(0.0), |_| number("width"))?,
+                            height: attribute("height").map_or(Ok(0.0), |_| number("height"))?,
+                        };
+                        // tile objects are placed by their bottom left corner
+                        if attribute("gid").is_some() {
+                            object.y -= object.height;
+                        }
+                        map.objects.push(object);
+                    }
+                    _ => {}
+                }
+
+                elements.push(element);
+            }
+            XmlEvent::EndElement { .. } => {
+                let element = elements.pop();
+                if element.as_deref() == Some("tile") {
+                    tile_id = None;
+                }
+            }
+            XmlEvent::Characters(text)
+                if layers == 1 && elements.last().map(String::as_str) == Some("data") =>
+            {
+                let gids = text
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|gid| !gid.is_empty())
+                    .map(|gid| parse_attribute("data", "csv", Some(gid)))
+                    .collect::<Result<Vec<u32>, _>>()?;
+                map.layer.get_or_insert_with(Vec::new).extend(gids);
+            }
+            _ => {}
+        }
+    }
+
+    map.into_tile_map()
+}
+
+/// Parse the value of an attribute, which is required.
+fn parse_attribute<T: FromStr>(
+    element: &str,
+    key: &str,
+    value: Option<&str>,
+) -> Result<T, TiledError> {
+    value.and_then(|value| value.parse().ok()).ok_or_else(|| {
+        TiledError::Malformed(format!(
+            "missing or invalid attribute '{key}' of <{element}>"
+        ))
+    })
+}
+
+#[derive(Deserialize)]
+struct JsonProperty {
+    name: String,
+    #[serde(default, rename = "type")]
+    kind: String,
+    value: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct JsonTile {
+    id: u32,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Deserialize)]
+struct JsonTileset {
+    #[serde(default)]
+    firstgid: u32,
+    source: Option<String>,
+    #[serde(default)]
+    tiles: Vec<JsonTile>,
+}
+
+#[derive(Deserialize)]
+struct JsonObject {
+    #[serde(default)]
+    name: String,
+    #[serde(default, rename = "type")]
+    kind: String,
+    #[serde(default)]
+    class: String,
+    x: f32,
+    y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+    gid: Option<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum JsonLayer {
+    #[serde(rename = "tilelayer")]
+    Tiles {
+        data: Option<serde_json::Value>,
+        encoding: Option<String>,
+    },
+    #[serde(rename = "objectgroup")]
+    Objects { objects: Vec<JsonObject> },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+struct JsonMap {
+    width: usize,
+    height: usize,
+    tilewidth: f32,
+    tileheight: f32,
+    orientation: Option<String>,
+    #[serde(default)]
+    infinite: bool,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+    #[serde(default)]
+    tilesets: Vec<JsonTileset>,
+    layers: Vec<JsonLayer>,
+}
+
+/// Text of a property value, since the header only contains text.
+fn property_value(value: serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(value) => value,
+        value => value.to_string(),
+    }
+}
+
+/// Import a map saved by Tiled in its JSON format (`.tmj`). The same restrictions as for
+/// [`import_tmx`] apply, the tile layer has to be stored as an array.
+pub fn import_tmj(content: &str) -> Result<TileMap, TiledError> {
+    let json = serde_json::from_str::<JsonMap>(content)?;
+
+    if json.orientation.is_some_and(|value| value != "orthogonal") {
+        return Err(TiledError::Unsupported("non-orthogonal maps".into()));
+    }
+    if json.infinite {
+        return Err(TiledError::Unsupported("infinite maps".into()));
+    }
+
+    let mut map = TiledMap {
+        width: json.width,
+        height: json.height,
+        tile_width: json.tilewidth,
+        tile_height: json.tileheight,
+        properties: json
+            .properties
+            .into_iter()
+            .map(|property| TiledProperty {
+                is_color: property.kind == "color",
+                name: property.name,
+                value: property_value(property.value),
+            })
+            .collect(),
+        ..Default::default()
+    };
+
+    for tileset in json.tilesets {
+        if tileset.source.is_some() {
+            return Err(TiledError::Unsupported("external tilesets".into()));
+        }
+        for tile in tileset.tiles {
+            let value = tile
+                .properties
+                .into_iter()
+                .find(|property| property.name == TILE_PROPERTY);
+            if let Some(value) = value {
+                map.tiles
+                    .insert(tileset.firstgid + tile.id, property_value(value.value));
+            }
+        }
+    }
+
+    for layer in json.layers {
+        match layer {
+            JsonLayer::Tiles { data, encoding } if map.layer.is_none() => {
+                if encoding.is_some_and(|encoding| encoding != "csv") {
+                    return Err(TiledError::Unsupported("base64 encoded tile layers".into()));
+                }
+                let Some(data) = data else {
+                    return Err(TiledError::Unsupported("infinite maps".into()));
+                };
+                map.layer = Some(serde_json::from_value(data)?);
+            }
+            JsonLayer::Objects { objects } => {
+                map.objects.extend(objects.into_iter().map(|object| {
+                    let kind = [object.kind, object.class, object.name]
+                        .into_iter()
+                        .find(|kind| !kind.is_empty())
+                        .unwrap_or_default();
+                    // tile objects are placed by their bottom left corner
+                    let y = match object.gid {
+                        Some(_) => object.y - object.height,
+                        None => object.y,
+                    };
+                    TiledObject {
+                        kind,
+                        x: object.x,
+                        y,
+                        width: object.width,
+                        height: object.height,
+                    }
+                }));
+            }
+            _ => {}
+        }
+    }
+
+    map.into_tile_map()
+}
+
+/// Import a map saved by Tiled, choosing the format by the extension of its path.
+pub fn import_tiled(path: &Path, content: &str) -> Result<TileMap, TiledError> {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("tmj" | "json") => import_tmj(content),
+        _ => import_tmx(content),
+    }
+}
+
+/// Loader for maps made with the Tiled map editor (see [`import_tmx`]), so they can be played
+/// like maps in our text format.
+#[derive(Default)]
+pub struct TiledMapLoader;
+
+impl AssetLoader for TiledMapLoader {
+    type Asset = TileMap;
+    type Settings = ();
+    type Error = TiledError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut content = String::new();
+        reader.read_to_string(&mut content).await?;
+        import_tiled(load_context.path(), &content)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        TILED_EXTENSIONS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::UVec2;
+
+    use crate::{
+        entities::GhostType,
+        map::{import_tmj, import_tmx, TileMap, TiledError},
+    };
+
+    const TILESET_TMX: &str = r##"
+        <tileset firstgid="1" name="pacman" tilewidth="8" tileheight="8" tilecount="3">
+            <tile id="0"><properties><property name="tile" value="#"/></properties></tile>
+            <tile id="1"><properties><property name="tile" value="."/></properties></tile>
+            <tile id="2"><properties><property name="tile" value="-"/></properties></tile>
+        </tileset>"##;
+
+    fn tmx(data: &str, objects: &str) -> String {
+        format!(
+            r##"<?xml version="1.0" encoding="UTF-8"?>
+            <map version="1.10" orientation="orthogonal" width="5" height="4" tilewidth="8" tileheight="8" infinite="0">
+                <properties>
+                    <property name="name" value="#Imported"/>
+                    <property name="wall_color" type="color" value="#ff00ff00"/>
+                </properties>
+                {TILESET_TMX}
+                <layer id="1" name="Tiles" width="5" height="4">
+                    <data encoding="csv">{data}</data>
+                </layer>
+                <objectgroup id="2" name="Spawns">{objects}</objectgroup>
+            </map>"##
+        )
+    }
+
+    const DATA: &str = "1,1,1,1,1,\n1,2,3,2,1,\n1,0,0,0,1,\n1,1,1,1,1";
+
+    const OBJECTS: &str = r#"
+        <object id="1" name="pacman" x="8" y="8"><point/></object>
+        <object id="2" type="blinky" x="24" y="16" width="8" height="8"/>
+        <object id="3" name="ghost_house" x="8" y="16" width="24" height="8"/>"#;
+
+    fn expected() -> TileMap {
+        "name: #Imported\nwall_color: #00ff00\n#####\n#@-.#\n#HHB#\n#####"
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_import_tmx() {
+        let map = import_tmx(&tmx(DATA, OBJECTS)).unwrap();
+
+        assert_eq!(map, expected());
+        assert_eq!(map.spawns().ghost_house.len(), 2);
+    }
+
+    #[test]
+    fn test_import_tmj() {
+        let json = r##"{
+            "width": 5, "height": 4, "tilewidth": 8, "tileheight": 8,
+            "orientation": "orthogonal", "infinite": false,
+            "properties": [
+                {"name": "name", "type": "string", "value": "#Imported"},
+                {"name": "wall_color", "type": "color", "value": "#ff00ff00"}
+            ],
+            "tilesets": [{"firstgid": 1, "tiles": [
+                {"id": 0, "properties": [{"name": "tile", "type": "string", "value": "#"}]},
+                {"id": 1, "properties": [{"name": "tile", "type": "string", "value": "."}]},
+                {"id": 2, "properties": [{"name": "tile", "type": "string", "value": "-"}]}
+            ]}],
+            "layers": [
+                {"type": "tilelayer", "name": "Tiles", "width": 5, "height": 4,
+                 "data": [1,1,1,1,1, 1,2,3,2,1, 1,0,0,0,1, 1,1,1,1,1]},
+                {"type": "objectgroup", "name": "Spawns", "objects": [
+                    {"id": 1, "name": "pacman", "x": 8, "y": 8, "point": true},
+                    {"id": 2, "name": "", "type": "blinky", "gid": 1, "x": 24, "y": 24, "width": 8, "height": 8},
+                    {"id": 3, "name": "ghost_house", "x": 8, "y": 16, "width": 24, "height": 8}
+                ]}
+            ]
+        }"##;
+
+        assert_eq!(import_tmj(json).unwrap(), expected());
+    }
+
+    #[test]
+    fn test_import_flipped_tiles() {
+        // the same wall, flipped horizontally and vertically
+        let data = DATA.replacen("1,1,1,1,1", "2147483649,1073741825,1,1,1", 1);
+        let map = import_tmx(&tmx(&data, OBJECTS)).unwrap();
+
+        assert_eq!(map, expected());
+        assert_eq!(map.spawns().pacman, UVec2::new(1, 2));
+        assert_eq!(
+            map.spawns().ghosts,
+            vec![(GhostType::Blinky, UVec2::new(3, 1))]
+        );
+    }
+
+    #[test]
+    fn test_import_example() {
+        let map = import_tmx(include_str!("../../assets/lighthouse.tmx")).unwrap();
+
+        assert_eq!(
+            map,
+            include_str!("../../assets/lighthouse.txt")
+                .parse::<TileMap>()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_import_invalid_maps() {
+        assert!(matches!(
+            import_tmx(&tmx(&DATA.replace('3', "7"), OBJECTS)),
+            Err(TiledError::UnknownTile(7))
+        ));
+        assert!(matches!(
+            import_tmx(&tmx(DATA, r#"<object name="mushroom" x="8" y="8"/>"#)),
+            Err(TiledError::UnknownObject(kind)) if kind == "mushroom"
+        ));
+        assert!(matches!(
+            import_tmx(&tmx(DATA, r#"<object name="pacman" x="80" y="8"/>"#)),
+            Err(TiledError::ObjectOutsideMap(_))
+        ));
+        assert!(matches!(
+            import_tmx(&tmx(DATA, "")),
+            Err(TiledError::Map(_))
+        ));
+        assert!(matches!(
+            import_tmx(&tmx(DATA, OBJECTS).replace("\"csv\"", "\"base64\"")),
+            Err(TiledError::Unsupported(_))
+        ));
+    }
+}
@@ -1,63 +1,309 @@
-use std::fs;
+use std::{fmt, ops::Index, str::FromStr};
 
 use bevy::prelude::*;
 
-use super::Tile;
+use super::{MapMeta, Marker, ParseMapError, ParseMapErrorKind, SpawnPoints, Tile};
 
 pub type Column = Vec<Tile>;
 
 /// A map representing all tiles present on the map.
-#[derive(Resource, Clone)]
+#[derive(Asset, TypePath, Resource, Clone, Debug, PartialEq)]
 pub struct TileMap {
     _tiles: Vec<Column>,
+    spawns: SpawnPoints,
+    meta: MapMeta,
 }
 
 impl From<Vec<Vec<Tile>>> for TileMap {
     fn from(tiles: Vec<Vec<Tile>>) -> Self {
-        Self { _tiles: tiles }
+        Self {
+            _tiles: tiles,
+            spawns: SpawnPoints::default(),
+            meta: MapMeta::default(),
+        }
     }
 }
 
-impl TileMap {
-    pub fn from_string(data: &str) -> Self {
-        let content = fs::read_to_string(data).expect("Should have been able to read the map");
+impl FromStr for TileMap {
+    type Err = ParseMapError;
+
+    /// Parse a map from its text representation. The text starts with an optional header of
+    /// `key: value` lines (see [`MapMeta`]), followed by the rows of the map. The first row is
+    /// the top row of the map.
+    fn from_str(content: &str) -> Result<Self, Self::Err> {
+        let lines = content.lines().collect::<Vec<_>>();
+
+        let mut meta = MapMeta::default();
+        let mut first_row = 0;
+
+        // ':' is neither a tile nor a marker, so it only occurs in the header
+        for (index, line) in lines.iter().enumerate() {
+            if line.is_empty() {
+                first_row = index + 1;
+                continue;
+            }
+            let Some((key, value)) = line.split_once(':') else {
+                break;
+            };
+            let key = key.trim();
+            meta.set(key, value.trim())
+                .map_err(|kind| ParseMapError::new(index + 1, 1, kind))?;
+            first_row = index + 1;
+        }
+
+        let rows = &lines[first_row..];
+        let width = rows.first().map_or(0, |line| line.chars().count());
+        if width == 0 {
+            return Err(ParseMapError::new(
+                first_row + 1,
+                1,
+                ParseMapErrorKind::Empty,
+            ));
+        }
 
-        let lines = content.lines().rev();
+        let height = rows.len();
+        let mut columns = vec![Column::with_capacity(height); width];
+        let mut pacman = None;
+        let mut spawns = SpawnPoints::default();
+        // number of occurrences and the first position (line and column) of every teleporter
+        let mut teleporters = [(0, 0, 0); 10];
 
-        let mut columns = vec![];
+        for (index, line) in rows.iter().enumerate() {
+            let line_number = first_row + index + 1;
+            // the text starts with the top row, but our y axis points upwards
+            let y = (height - index - 1) as u32;
+            let found = line.chars().count();
 
-        for line in lines {
-            let letters = line.chars();
-            for (x, letter) in letters.enumerate() {
-                if columns.len() <= x {
-                    columns.push(vec![]);
+            for (x, letter) in line.chars().enumerate() {
+                let error = |kind| ParseMapError::new(line_number, x + 1, kind);
+
+                if x >= width {
+                    return Err(error(ParseMapErrorKind::RaggedRow {
+                        expected: width,
+                        found,
+                    }));
+                }
+
+                if let Some(tile) = Tile::from_char(letter) {
+                    if let Tile::Teleporter(number) = tile {
+                        let (count, ..) = &mut teleporters[number as usize];
+                        *count += 1;
+                        if *count > 2 {
+                            return Err(error(ParseMapErrorKind::UnpairedTeleporter(letter)));
+                        }
+                        if *count == 1 {
+                            teleporters[number as usize] = (1, line_number, x + 1);
+                        }
+                    }
+                    columns[x].push(tile);
+                    continue;
+                }
+
+                let Some(marker) = Marker::from_char(letter) else {
+                    return Err(error(ParseMapErrorKind::UnknownTile(letter)));
+                };
+
+                let position = UVec2::new(x as u32, y);
+                let duplicate = match marker {
+                    Marker::Pacman => pacman.replace(position).is_some(),
+                    Marker::Ghost(ghost) => {
+                        let duplicate = spawns.ghosts.iter().any(|(other, _)| *other == ghost);
+                        spawns.ghosts.push((ghost, position));
+                        duplicate
+                    }
+                    Marker::Fruit => spawns.fruit.replace(position).is_some(),
+                    Marker::GhostHouse => {
+                        spawns.ghost_house.push(position);
+                        false
+                    }
+                };
+
+                if duplicate {
+                    return Err(error(ParseMapErrorKind::DuplicateMarker(letter)));
                 }
-                columns[x].push(Tile::from_char(letter));
+
+                columns[x].push(Tile::Empty);
+            }
+
+            if found < width {
+                return Err(ParseMapError::new(
+                    line_number,
+                    found + 1,
+                    ParseMapErrorKind::RaggedRow {
+                        expected: width,
+                        found,
+                    },
+                ));
             }
         }
 
-        // TODO: evaluate (or think about) whether this is needed
-        assert!(
-            !columns.is_empty(),
-            "Expected map to have at least one column"
-        );
+        for (number, (count, line, column)) in teleporters.into_iter().enumerate() {
+            if count == 1 {
+                return Err(ParseMapError::new(
+                    line,
+                    column,
+                    ParseMapErrorKind::UnpairedTeleporter(Tile::Teleporter(number as u8).to_char()),
+                ));
+            }
+        }
 
-        let rows = columns[0].len();
-        for (x, column) in columns.iter().enumerate() {
-            assert!(
-                column.len() == rows,
-                "Expected all columns to have the same amount of rows! (failed at column {}; {} instead of {})",
-                x, column.len(), rows
-            );
+        let Some(pacman) = pacman else {
+            return Err(ParseMapError::new(
+                first_row + 1,
+                1,
+                ParseMapErrorKind::MissingMarker(Marker::Pacman.to_char()),
+            ));
+        };
+        spawns.pacman = pacman;
+
+        for column in columns.iter_mut() {
+            column.reverse();
         }
 
-        TileMap::from(columns)
+        Ok(Self {
+            _tiles: columns,
+            spawns,
+            meta,
+        })
     }
+}
 
-    pub fn columns(&self) -> std::slice::Iter<Column> {
+impl fmt::Display for TileMap {
+    /// Write the map in the same format as it is parsed by [`TileMap::from_str`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.meta)?;
+
+        for y in (0..self.height()).rev() {
+            for (x, tile) in self.row(y).enumerate() {
+                let position = UVec2::new(x as u32, y as u32);
+                let character = match self.spawns.marker_at(position) {
+                    Some(marker) => marker.to_char(),
+                    None => tile.to_char(),
+                };
+                write!(f, "{character}")?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl TileMap {
+    /// Create a map from its columns (from left to right, each from bottom to top), its markers
+    /// and its header.
+    pub fn new(tiles: Vec<Column>, spawns: SpawnPoints, meta: MapMeta) -> Self {
+        Self {
+            _tiles: tiles,
+            spawns,
+            meta,
+        }
+    }
+
+    /// Number of columns of this map.
+    pub fn width(&self) -> usize {
+        self._tiles.len()
+    }
+
+    /// Number of rows of this map.
+    pub fn height(&self) -> usize {
+        self._tiles.first().map_or(0, Vec::len)
+    }
+
+    /// Positions of pacman, the ghosts and other markers on this map.
+    pub fn spawns(&self) -> &SpawnPoints {
+        &self.spawns
+    }
+
+    /// Mutable access to the markers of this map.
+    pub fn spawns_mut(&mut self) -> &mut SpawnPoints {
+        &mut self.spawns
+    }
+
+    /// Additional information given in the header of the map.
+    pub fn meta(&self) -> &MapMeta {
+        &self.meta
+    }
+
+    /// Mutable access to the header of this map.
+    pub fn meta_mut(&mut self) -> &mut MapMeta {
+        &mut self.meta
+    }
+
+    /// Replace the tile at the specified position. Positions outside of the map are ignored.
+    pub fn set(&mut self, x: usize, y: usize, tile: Tile) {
+        if let Some(current) = self._tiles.get_mut(x).and_then(|column| column.get_mut(y)) {
+            *current = tile;
+        }
+    }
+
+    /// Get the position of the teleporter paired with the teleporter at the specified position.
+    /// Returns `None`, if there is no teleporter at this position.
+    pub fn teleport_target(&self, x: i32, y: i32) -> Option<UVec2> {
+        let Some(Tile::Teleporter(number)) = self.at(x, y) else {
+            return None;
+        };
+
+        self.positions_of(Tile::Teleporter(number))
+            .find(|other| other.as_ivec2() != IVec2::new(x, y))
+    }
+
+    pub fn columns(&self) -> std::slice::Iter<'_, Column> {
         self._tiles.iter()
     }
 
+    /// Iterate over the tiles of a row from left to right. A row outside of the map is empty.
+    pub fn row(&self, y: usize) -> impl Iterator<Item = Tile> + '_ {
+        self.columns()
+            .filter_map(move |column| column.get(y).copied())
+    }
+
+    /// Iterate over all tiles together with their positions, column by column starting at the
+    /// bottom left.
+    pub fn iter(&self) -> impl Iterator<Item = (UVec2, Tile)> + '_ {
+        self.columns().enumerate().flat_map(|(x, column)| {
+            column
+                .iter()
+                .enumerate()
+                .map(move |(y, tile)| (UVec2::new(x as u32, y as u32), *tile))
+        })
+    }
+
+    /// Iterate over the positions of all tiles of the given type.
+    pub fn positions_of(&self, tile: Tile) -> impl Iterator<Item = UVec2> + '_ {
+        self.iter()
+            .filter(move |(_, other)| *other == tile)
+            .map(|(position, _)| position)
+    }
+
+    /// Count the tiles of the given type.
+    pub fn count(&self, tile: Tile) -> usize {
+        self.positions_of(tile).count()
+    }
+
+    /// Iterate over the (up to four) tiles next to the specified position, starting above it and
+    /// going clockwise. If `wrap` is set, neighbours beyond the border are taken from the opposite
+    /// edge (like pacman leaving through a tunnel), otherwise they are skipped.
+    pub fn neighbours(
+        &self,
+        position: UVec2,
+        wrap: bool,
+    ) -> impl Iterator<Item = (UVec2, Tile)> + '_ {
+        let (width, height) = (self.width() as i32, self.height() as i32);
+        let position = position.as_ivec2();
+
+        [IVec2::Y, IVec2::X, IVec2::NEG_Y, IVec2::NEG_X]
+            .into_iter()
+            .filter_map(move |offset| {
+                let mut next = position + offset;
+                if wrap {
+                    next = IVec2::new(next.x.rem_euclid(width), next.y.rem_euclid(height));
+                }
+                let tile = self.at(next.x, next.y)?;
+                Some((next.as_uvec2(), tile))
+            })
+    }
+
     /// Get the tile at the specified position. If there is no tile or the indices are negative,
     /// this function returns None.
     pub fn at(&self, x: i32, y: i32) -> Option<Tile> {
@@ -65,10 +311,295 @@ impl TileMap {
             return None;
         }
         match self._tiles.get(x as usize) {
-            Some(column) => {
-                return column.get(y as usize).copied();
-            }
+            Some(column) => column.get(y as usize).copied(),
             None => None,
         }
     }
 }
+
+impl Index<UVec2> for TileMap {
+    type Output = Tile;
+
+    /// Get the tile at the specified position.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if the position is outside of the map.
+    fn index(&self, position: UVec2) -> &Self::Output {
+        &self._tiles[position.x as usize][position.y as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::UVec2;
+
+    use crate::{
+        entities::{GhostType, MovementDirection},
+        lighthouse::LighthouseColor,
+        map::{Marker, ParseMapError, ParseMapErrorKind, Tile, TileMap},
+    };
+
+    #[test]
+    fn test_parse_map() {
+        let map = "#.#\n#@#\n".parse::<TileMap>().unwrap();
+
+        assert_eq!(map.width(), 3);
+        assert_eq!(map.height(), 2);
+        assert_eq!(map.at(1, 1), Some(Tile::Coin));
+        assert_eq!(map.at(1, 0), Some(Tile::Empty));
+        assert_eq!(map.at(3, 0), None);
+        assert_eq!(map.spawns().pacman, UVec2::new(1, 0));
+    }
+
+    #[test]
+    fn test_parse_empty_map() {
+        assert_eq!(
+            "".parse::<TileMap>().err(),
+            Some(ParseMapError::new(1, 1, ParseMapErrorKind::Empty))
+        );
+    }
+
+    #[test]
+    fn test_parse_unknown_tile() {
+        assert_eq!(
+            "###\n#x#\n###".parse::<TileMap>().err(),
+            Some(ParseMapError::new(
+                2,
+                2,
+                ParseMapErrorKind::UnknownTile('x')
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_ragged_rows() {
+        assert_eq!(
+            "###\n#\n###".parse::<TileMap>().err(),
+            Some(ParseMapError::new(
+                2,
+                2,
+                ParseMapErrorKind::RaggedRow {
+                    expected: 3,
+                    found: 1
+                }
+            ))
+        );
+        assert_eq!(
+            "###\n###\n####".parse::<TileMap>().err(),
+            Some(ParseMapError::new(
+                3,
+                4,
+                ParseMapErrorKind::RaggedRow {
+                    expected: 3,
+                    found: 4
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_header_and_markers() {
+        let map = "name: Test\nwall_color: #ff0080\ntick_speed: 4\n\n#####\n#BHF#\n#@.I#\n#####"
+            .parse::<TileMap>()
+            .unwrap();
+
+        assert_eq!(map.meta().name.as_deref(), Some("Test"));
+        assert_eq!(
+            map.meta().wall_color,
+            Some(LighthouseColor::Explicit {
+                r: 255,
+                g: 0,
+                b: 128
+            })
+        );
+        assert_eq!(map.meta().tick_speed, Some(4.0));
+        assert_eq!(map.height(), 4);
+
+        let spawns = map.spawns();
+        assert_eq!(spawns.pacman, UVec2::new(1, 1));
+        assert_eq!(
+            spawns.ghosts,
+            vec![
+                (GhostType::Blinky, UVec2::new(1, 2)),
+                (GhostType::Inky, UVec2::new(3, 1))
+            ]
+        );
+        assert_eq!(spawns.fruit, Some(UVec2::new(3, 2)));
+        assert_eq!(spawns.ghost_house, vec![UVec2::new(2, 2)]);
+        assert_eq!(map.at(1, 1), Some(Tile::Empty));
+    }
+
+    #[test]
+    fn test_parse_invalid_header() {
+        assert_eq!(
+            "speed: 3\n#@#".parse::<TileMap>().err(),
+            Some(ParseMapError::new(
+                1,
+                1,
+                ParseMapErrorKind::UnknownHeaderKey("speed".into())
+            ))
+        );
+        assert_eq!(
+            "name: a\nwall_color: blue\n#@#".parse::<TileMap>().err(),
+            Some(ParseMapError::new(
+                2,
+                1,
+                ParseMapErrorKind::InvalidHeaderValue {
+                    key: "wall_color".into(),
+                    value: "blue".into()
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_markers() {
+        assert_eq!(
+            "#@@#".parse::<TileMap>().err(),
+            Some(ParseMapError::new(
+                1,
+                3,
+                ParseMapErrorKind::DuplicateMarker('@')
+            ))
+        );
+        assert_eq!(
+            "#..#".parse::<TileMap>().err(),
+            Some(ParseMapError::new(
+                1,
+                1,
+                ParseMapErrorKind::MissingMarker('@')
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_stock_maps() {
+        let lighthouse = include_str!("../../assets/lighthouse.txt")
+            .parse::<TileMap>()
+            .unwrap();
+        assert_eq!((lighthouse.width(), lighthouse.height()), (14, 14));
+        assert_eq!(lighthouse.spawns().ghosts.len(), 4);
+
+        let arcade = include_str!("../../assets/map.txt")
+            .parse::<TileMap>()
+            .unwrap();
+        assert_eq!((arcade.width(), arcade.height()), (19, 22));
+        assert_eq!(arcade.spawns().ghosts.len(), 4);
+    }
+
+    #[test]
+    fn test_parse_tunnels_and_teleporters() {
+        let map = "#####\n=1@1=\n#2#2#".parse::<TileMap>().unwrap();
+
+        assert_eq!(map.at(0, 1), Some(Tile::Tunnel));
+        assert_eq!(map.at(1, 1), Some(Tile::Teleporter(1)));
+        assert_eq!(map.teleport_target(1, 1), Some(UVec2::new(3, 1)));
+        assert_eq!(map.teleport_target(3, 0), Some(UVec2::new(1, 0)));
+        assert_eq!(map.teleport_target(0, 1), None);
+        assert_eq!(map.to_string(), "#####\n=1@1=\n#2#2#\n");
+
+        assert_eq!(
+            "#1@#".parse::<TileMap>().err(),
+            Some(ParseMapError::new(
+                1,
+                2,
+                ParseMapErrorKind::UnpairedTeleporter('1')
+            ))
+        );
+        assert_eq!(
+            "3@3\n#3#".parse::<TileMap>().err(),
+            Some(ParseMapError::new(
+                2,
+                2,
+                ParseMapErrorKind::UnpairedTeleporter('3')
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_special_tiles() {
+        let map = "#######\n#~^>v<#\n#@ + -#\n#######"
+            .parse::<TileMap>()
+            .unwrap();
+
+        assert_eq!(map.at(1, 2), Some(Tile::Slow));
+        assert_eq!(map.at(2, 2), Some(Tile::OneWay(MovementDirection::Up)));
+        assert_eq!(map.at(5, 2), Some(Tile::OneWay(MovementDirection::Left)));
+        assert_eq!(map.at(3, 1), Some(Tile::NoGhosts));
+        assert_eq!(map.to_string(), "#######\n#~^>v<#\n#@ + -#\n#######\n");
+    }
+
+    #[test]
+    fn test_edit_map() {
+        let mut map = "#####\n#B.F#\n#@..#\n#####".parse::<TileMap>().unwrap();
+
+        map.set(2, 2, Tile::Wall);
+        map.spawns_mut()
+            .place(Marker::Ghost(GhostType::Blinky), UVec2::new(3, 1));
+        map.spawns_mut().place(Marker::Pacman, UVec2::new(2, 1));
+        map.spawns_mut().remove_at(UVec2::new(3, 2));
+
+        assert_eq!(map.to_string(), "#####\n# # #\n# @B#\n#####\n");
+    }
+
+    #[test]
+    fn test_display_roundtrip() {
+        let text = "name: Roundtrip\ntick_speed: 2.5\n#####\n#BHF#\n#@ .#\n#####\n";
+        let map = text.parse::<TileMap>().unwrap();
+
+        assert_eq!(map.to_string(), text);
+        assert_eq!(map.to_string().parse::<TileMap>().unwrap(), map);
+    }
+
+    #[test]
+    fn test_query_tiles() {
+        let map = "=.#.=\n#@..#\n#####".parse::<TileMap>().unwrap();
+
+        assert_eq!(map[UVec2::new(1, 2)], Tile::Coin);
+        assert_eq!(
+            map.row(2).collect::<Vec<_>>(),
+            vec![
+                Tile::Tunnel,
+                Tile::Coin,
+                Tile::Wall,
+                Tile::Coin,
+                Tile::Tunnel
+            ]
+        );
+        assert_eq!(map.row(3).count(), 0);
+        assert_eq!(map.iter().count(), 15);
+        assert_eq!(map.iter().nth(4), Some((UVec2::new(1, 1), Tile::Empty)));
+        assert_eq!(map.count(Tile::Coin), 4);
+        assert_eq!(map.count(Tile::Wall), 8);
+        assert_eq!(
+            map.positions_of(Tile::Tunnel).collect::<Vec<_>>(),
+            vec![UVec2::new(0, 2), UVec2::new(4, 2)]
+        );
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let map = "=.#.=\n#@..#\n#####".parse::<TileMap>().unwrap();
+
+        assert_eq!(
+            map.neighbours(UVec2::new(1, 1), false).collect::<Vec<_>>(),
+            vec![
+                (UVec2::new(1, 2), Tile::Coin),
+                (UVec2::new(2, 1), Tile::Coin),
+                (UVec2::new(1, 0), Tile::Wall),
+                (UVec2::new(0, 1), Tile::Wall)
+            ]
+        );
+        assert_eq!(map.neighbours(UVec2::new(0, 2), false).count(), 2);
+        assert_eq!(
+            map.neighbours(UVec2::new(0, 2), true).collect::<Vec<_>>(),
+            vec![
+                (UVec2::new(0, 0), Tile::Wall),
+                (UVec2::new(1, 2), Tile::Coin),
+                (UVec2::new(0, 1), Tile::Wall),
+                (UVec2::new(4, 2), Tile::Tunnel)
+            ]
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use std::fmt;
+
+use bevy::prelude::*;
+
+use crate::entities::GhostType;
+
+use super::{Navigation, Tile, TileMap, Walker};
+
+/// A problem found while validating a map. Positions are given in map coordinates, i.e., `(0, 0)`
+/// is the bottom left tile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapProblem {
+    /// A tunnel at the border leads to the opposite edge, but the tile there (where pacman would
+    /// come out) is a wall.
+    UnmatchedOpening { position: UVec2, opposite: UVec2 },
+    /// Pacman spawns inside a wall.
+    PacmanInWall { position: UVec2 },
+    /// A coin can not be reached from the spawn of pacman.
+    UnreachableCoin { position: UVec2 },
+    /// A ghost can not leave its spawn to get to pacman.
+    GhostWalledIn { ghost: GhostType, position: UVec2 },
+}
+
+impl fmt::Display for MapProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapProblem::UnmatchedOpening { position, opposite } => write!(
+                f,
+                "({}, {}): tunnel leads to the opposite tile ({}, {}), which is a wall",
+                position.x, position.y, opposite.x, opposite.y
+            ),
+            MapProblem::PacmanInWall { position } => {
+                write!(
+                    f,
+                    "({}, {}): pacman spawns inside a wall",
+                    position.x, position.y
+                )
+            }
+            MapProblem::UnreachableCoin { position } => write!(
+                f,
+                "({}, {}): coin can not be reached by pacman",
+                position.x, position.y
+            ),
+            MapProblem::GhostWalledIn { ghost, position } => write!(
+                f,
+                "({}, {}): {ghost:?} can not reach pacman from its spawn",
+                position.x, position.y
+            ),
+        }
+    }
+}
+
+/// Check a map for problems, which make it unplayable. All problems found are returned at once.
+pub fn validate(map: &TileMap) -> Result<(), Vec<MapProblem>> {
+    let mut problems = vec![];
+
+    check_borders(map, &mut problems);
+    check_reachability(map, &mut problems);
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems)
+    }
+}
+
+fn is_open(map: &TileMap, x: i32, y: i32) -> bool {
+    map.at(x, y)
+        .is_some_and(|tile| Walker::Pacman.can_enter(tile))
+}
+
+/// Every tunnel on the border needs an open tile on the opposite edge, since pacman wraps around
+/// when leaving the map through it.
+fn check_borders(map: &TileMap, problems: &mut Vec<MapProblem>) {
+    let width = map.width() as u32;
+    let height = map.height() as u32;
+
+    for position in map.positions_of(Tile::Tunnel) {
+        let UVec2 { x, y } = position;
+        let mut opposites = vec![];
+        if x == 0 || x == width - 1 {
+            opposites.push(UVec2::new(width - 1 - x, y));
+        }
+        if y == 0 || y == height - 1 {
+            opposites.push(UVec2::new(x, height - 1 - y));
+        }
+
+        for opposite in opposites {
+            if !is_open(map, opposite.x as i32, opposite.y as i32) {
+                problems.push(MapProblem::UnmatchedOpening { position, opposite });
+            }
+        }
+    }
+}
+
+/// All coins and ghosts have to be connected to pacman.
+fn check_reachability(map: &TileMap, problems: &mut Vec<MapProblem>) {
+    let spawns = map.spawns();
+    let pacman = spawns.pacman;
+
+    if !is_open(map, pacman.x as i32, pacman.y as i32) {
+        problems.push(MapProblem::PacmanInWall { position: pacman });
+        return;
+    }
+
+    let navigation = Navigation::from(map);
+    let Some(reachable) = navigation.distances_from(pacman, Walker::Pacman) else {
+        return;
+    };
+
+    for position in map.positions_of(Tile::Coin) {
+        if reachable.get(position).is_none() {
+            problems.push(MapProblem::UnreachableCoin { position });
+        }
+    }
+
+    // ghosts may pass doors, e.g., to leave the ghost house
+    for &(ghost, position) in &spawns.ghosts {
+        if navigation.path(position, pacman, Walker::Ghost).is_none() {
+            problems.push(MapProblem::GhostWalledIn { ghost, position });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::UVec2;
+
+    use crate::{
+        entities::GhostType,
+        map::{validate, MapProblem, TileMap},
+    };
+
+    fn problems(map: &str) -> Vec<MapProblem> {
+        let map = map.parse::<TileMap>().unwrap();
+
+        validate(&map).err().unwrap_or_default()
+    }
+
+    #[test]
+    fn test_valid_map() {
+        assert_eq!(problems("#####\n#@..#\n.B#..\n#####"), vec![]);
+    }
+
+    #[test]
+    fn test_unmatched_opening() {
+        assert_eq!(
+            problems("#####\n#@..=\n#####"),
+            vec![MapProblem::UnmatchedOpening {
+                position: UVec2::new(4, 1),
+                opposite: UVec2::new(0, 1)
+            }]
+        );
+    }
+
+    #[test]
+    fn test_reachability() {
+        assert_eq!(
+            problems("######\n#@#.I#\n######"),
+            vec![
+                MapProblem::UnreachableCoin {
+                    position: UVec2::new(3, 1)
+                },
+                MapProblem::GhostWalledIn {
+                    ghost: GhostType::Inky,
+                    position: UVec2::new(4, 1)
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reachability_through_tunnels_and_teleporters() {
+        assert_eq!(problems("######\n=@#..=\n######"), vec![]);
+        assert_eq!(problems("######\n#@1#1.\n######"), vec![]);
+        assert_eq!(problems("#####\n#@-B#\n#####"), vec![]);
+        assert_eq!(
+            problems("#####\n#@-.#\n#####"),
+            vec![MapProblem::UnreachableCoin {
+                position: UVec2::new(3, 1)
+            }]
+        );
+        assert_eq!(
+            problems("#####\n.@#.#\n#####"),
+            vec![MapProblem::UnreachableCoin {
+                position: UVec2::new(3, 1)
+            }]
+        );
+    }
+
+    #[test]
+    fn test_stock_maps_are_playable() {
+        for content in [
+            include_str!("../../assets/lighthouse.txt"),
+            include_str!("../../assets/map.txt"),
+        ] {
+            assert_eq!(problems(content), vec![]);
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use std::{f32::consts::FRAC_PI_2, str::FromStr};
+
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+    sprite::Anchor,
+};
+
+use crate::{
+    ascii::{AsciiSheet, SpriteAtlas},
+    map::TileMap,
+};
+
+use super::{wall_colors, wall_part_sprites, WallKind};
+
+/// Number of tiles in each direction, which are baked into a single image.
+pub const CHUNK_SIZE: usize = 16;
+
+/// Number of pixels per tile in the baked images (every wall part gets half of it).
+const TILE_PIXELS: usize = 32;
+
+/// How walls are drawn on the screen.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WallRendering {
+    /// Every part of every wall is its own sprite.
+    #[default]
+    Sprites,
+    /// All wall parts are baked into one image per chunk of the map, whenever the map changes.
+    Baked,
+}
+
+impl FromStr for WallRendering {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "sprites" => Ok(Self::Sprites),
+            "baked" => Ok(Self::Baked),
+            _ => Err(format!(
+                "unknown wall rendering '{value}', expected 'sprites' or 'baked'"
+            )),
+        }
+    }
+}
+
+/// Component for a sprite showing all walls of a chunk of the map.
+#[derive(Component)]
+pub struct WallChunk;
+
+/// An image containing the walls of the tiles from `origin` to `origin + size`.
+pub struct BakedChunk {
+    pub origin: UVec2,
+    pub size: UVec2,
+    pub image: Image,
+}
+
+/// Draw all walls of the map into images of at most `CHUNK_SIZE` by `CHUNK_SIZE` tiles. Chunks
+/// without any walls are skipped. The sprite sheet has to use 4 bytes per pixel.
+pub fn bake_wall_chunks(
+    map: &TileMap,
+    sheet: &Image,
+    layout: &TextureAtlasLayout,
+    atlas: &SpriteAtlas,
+) -> Vec<BakedChunk> {
+    let kinds = WallKind::classify(map);
+    let (_, color) = wall_colors(map);
+    let tint = color.to_srgba().to_u8_array();
+    let mut chunks = vec![];
+
+    for chunk_x in (0..map.width()).step_by(CHUNK_SIZE) {
+        for chunk_y in (0..map.height()).step_by(CHUNK_SIZE) {
+            let width = CHUNK_SIZE.min(map.width() - chunk_x);
+            let height = CHUNK_SIZE.min(map.height() - chunk_y);
+            let mut data = vec![0; width * height * TILE_PIXELS * TILE_PIXELS * 4];
+            let mut has_walls = false;
+
+            for (x, column) in kinds.iter().enumerate().skip(chunk_x).take(width) {
+                for (y, kind) in column.iter().enumerate().skip(chunk_y).take(height) {
+                    let Some(kind) = *kind else {
+                        continue;
+                    };
+                    has_walls = true;
+
+                    // images start at the top, but our y axis points upwards
+                    let left = (x - chunk_x) * TILE_PIXELS;
+                    let top = (chunk_y + height - 1 - y) * TILE_PIXELS;
+
+                    for (part, sprite, rotation) in wall_part_sprites(map, kind, x as i32, y as i32)
+                    {
+                        let offset = part.offset();
+                        let part_left = left + ((offset.x - 0.25) * TILE_PIXELS as f32) as usize;
+                        let part_top = top + ((0.75 - offset.y) * TILE_PIXELS as f32) as usize;
+                        let rect = layout.textures[atlas.index(sprite)];
+
+                        draw_part(
+                            &mut data,
+                            width * TILE_PIXELS,
+                            (part_left, part_top),
+                            sheet,
+                            rect,
+                            rotation,
+                            tint,
+                        );
+                    }
+                }
+            }
+
+            if !has_walls {
+                continue;
+            }
+
+            let image = Image::new(
+                Extent3d {
+                    width: (width * TILE_PIXELS) as u32,
+                    height: (height * TILE_PIXELS) as u32,
+                    depth_or_array_layers: 1,
+                },
+                TextureDimension::D2,
+                data,
+                TextureFormat::Rgba8UnormSrgb,
+                RenderAssetUsages::RENDER_WORLD,
+            );
+            chunks.push(BakedChunk {
+                origin: UVec2::new(chunk_x as u32, chunk_y as u32),
+                size: UVec2::new(width as u32, height as u32),
+                image,
+            });
+        }
+    }
+
+    chunks
+}
+
+/// Draw a sprite of the sheet rotated (by a multiple of 90 degrees) and tinted into the image.
+fn draw_part(
+    data: &mut [u8],
+    stride: usize,
+    (left, top): (usize, usize),
+    sheet: &Image,
+    rect: URect,
+    rotation: f32,
+    tint: [u8; 4],
+) {
+    let part_pixels = TILE_PIXELS / 2;
+    let sheet_width = sheet.width() as usize;
+    let source_size = rect.width() as f32;
+    let quarter_turns = (rotation / FRAC_PI_2).round() as i32;
+    let (sin, cos) = (quarter_turns as f32 * FRAC_PI_2).sin_cos();
+
+    for j in 0..part_pixels {
+        for i in 0..part_pixels {
+            // centered coordinates with the y axis pointing upwards, rotated back onto the sheet
+            let u = (i as f32 + 0.5) / part_pixels as f32 - 0.5;
+            let v = 0.5 - (j as f32 + 0.5) / part_pixels as f32;
+            let (u, v) = (u * cos + v * sin, v * cos - u * sin);
+
+            let source_x = ((u + 0.5) * source_size).clamp(0.0, source_size - 1.0) as u32;
+            let source_y = ((0.5 - v) * source_size).clamp(0.0, source_size - 1.0) as u32;
+            let source = ((rect.min.y + source_y) as usize * sheet_width
+                + (rect.min.x + source_x) as usize)
+                * 4;
+            let Some(pixel) = sheet.data.get(source..source + 4) else {
+                continue;
+            };
+            if pixel[3] == 0 {
+                continue;
+            }
+
+            let target = ((top + j) * stride + left + i) * 4;
+            for channel in 0..4 {
+                data[target + channel] = (pixel[channel] as u16 * tint[channel] as u16 / 255) as u8;
+            }
+        }
+    }
+}
+
+/// Bake the walls of the map into chunks, whenever the map changed. Since the sprite sheet might
+/// not be loaded yet, the walls are baked as soon as it is available.
+#[allow(clippy::too_many_arguments)]
+pub fn bake_walls(
+    mut commands: Commands,
+    rendering: Res<WallRendering>,
+    map: Option<Res<TileMap>>,
+    ascii: Res<AsciiSheet>,
+    layouts: Res<Assets<TextureAtlasLayout>>,
+    mut images: ResMut<Assets<Image>>,
+    chunks: Query<Entity, With<WallChunk>>,
+    mut pending: Local<bool>,
+) {
+    let Some(map) = map else {
+        return;
+    };
+    if *rendering != WallRendering::Baked {
+        return;
+    }
+    if map.is_changed() {
+        *pending = true;
+    }
+    if !*pending {
+        return;
+    }
+    let (Some(sheet), Some(layout)) = (images.get(&ascii.image), layouts.get(&ascii.layout)) else {
+        return;
+    };
+
+    let baked = bake_wall_chunks(&map, sheet, layout, &ascii.atlas);
+    *pending = false;
+
+    for entity in &chunks {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    for chunk in baked {
+        commands.spawn((
+            WallChunk,
+            SpriteBundle {
+                sprite: Sprite {
+                    anchor: Anchor::BottomLeft,
+                    custom_size: Some(chunk.size.as_vec2()),
+                    ..default()
+                },
+                transform: Transform::from_translation(chunk.origin.as_vec2().extend(2.0)),
+                texture: images.add(chunk.image),
+                ..default()
+            },
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        math::UVec2,
+        render::{
+            render_asset::RenderAssetUsages,
+            render_resource::{Extent3d, TextureDimension, TextureFormat},
+            texture::Image,
+        },
+    };
+
+    use crate::{
+        ascii::SpriteAtlas,
+        map::{bake_wall_chunks, TileMap, CHUNK_SIZE},
+    };
+
+    fn opaque_pixels(image: &Image, left: u32, top: u32, size: u32) -> usize {
+        let width = image.width();
+        (top..top + size)
+            .flat_map(|y| (left..left + size).map(move |x| ((y * width + x) * 4 + 3) as usize))
+            .filter(|alpha| image.data[*alpha] > 0)
+            .count()
+    }
+
+    #[test]
+    fn test_bake_wall_chunks() {
+        let sheet = Image::new_fill(
+            Extent3d {
+                width: 128,
+                height: 192,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &[255, 255, 255, 255],
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        );
+        let atlas = SpriteAtlas::builtin();
+        let map = "####\n#@.#\n####".parse::<TileMap>().unwrap();
+        let chunks = bake_wall_chunks(&map, &sheet, &atlas.layout(), &atlas);
+
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].origin, UVec2::ZERO);
+        assert_eq!(chunks[0].size, UVec2::new(4, 3));
+        assert_eq!(chunks[0].image.width(), 4 * 32);
+
+        // the tile of pacman is empty, the wall above it has sprites in its lower half
+        assert_eq!(opaque_pixels(&chunks[0].image, 32, 32, 32), 0);
+        assert_eq!(opaque_pixels(&chunks[0].image, 32, 0, 16), 0);
+        assert!(opaque_pixels(&chunks[0].image, 32, 16, 16) > 0);
+    }
+
+    #[test]
+    fn test_bake_large_map_in_chunks() {
+        let sheet = Image::default();
+        let atlas = SpriteAtlas::builtin();
+        let row = "#".repeat(CHUNK_SIZE + 1);
+        let map = format!("{row}\n@{}\n{row}", " ".repeat(CHUNK_SIZE))
+            .parse::<TileMap>()
+            .unwrap();
+        let chunks = bake_wall_chunks(&map, &sheet, &atlas.layout(), &atlas);
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[1].origin, UVec2::new(CHUNK_SIZE as u32, 0));
+        assert_eq!(chunks[1].size, UVec2::new(1, 3));
+    }
+}
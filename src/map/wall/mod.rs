@@ -1,208 +1,133 @@
+mod bake;
+mod wall_kind;
 mod wall_part;
 
+pub use self::bake::*;
+pub use self::wall_kind::*;
 pub use self::wall_part::*;
 
-use std::f32::consts::{FRAC_PI_2, PI};
-
 use bevy::{prelude::*, sprite::Anchor};
 
 use crate::{
     ascii::{AsciiSheet, SpriteIndices},
+    entities::GridPosition,
     lighthouse::{LighthouseBundle, LighthouseColor, LighthousePosition},
 };
 
 use super::TileMap;
 
-const GENERAL_OFFSET: Vec3 = Vec3 {
-    x: 0.25,
-    y: 0.25,
-    z: 0.0,
-};
+/// Color of the walls on the screen, if the map does not specify one.
+const DEFAULT_WALL_COLOR: Color = Color::srgb(0.0, 30.0 / 255.0, 1.0);
+
+/// Color of the walls on the lighthouse, if the map does not specify one.
+const DEFAULT_LIGHTHOUSE_WALL_COLOR: LighthouseColor = LighthouseColor::Inline(0, 0, 255);
 
 /// Component representing a wall on the map.
-/// It has (or at least should have) 4 children (one for each corner).
+/// Unless the walls are baked, it has up to 4 children (one for each visible corner).
 #[derive(Component)]
 pub struct WallTile;
 
-/// Determine the sprites for a wall depending on the sprites around it.
+/// Colors of the walls on the lighthouse and on the screen.
+pub fn wall_colors(tiles: &TileMap) -> (LighthouseColor, Color) {
+    match tiles.meta().wall_color {
+        Some(wall_color) => (wall_color, wall_color.into()),
+        None => (DEFAULT_LIGHTHOUSE_WALL_COLOR, DEFAULT_WALL_COLOR),
+    }
+}
+
+/// Determine the sprite (and its total rotation) of every part of the wall at the given position,
+/// which is visible.
+pub fn wall_part_sprites(
+    tiles: &TileMap,
+    kind: WallKind,
+    x: i32,
+    y: i32,
+) -> Vec<(WallPart, SpriteIndices, f32)> {
+    WallPart::ALL
+        .into_iter()
+        .filter_map(|part| {
+            let [one, two, three] = part
+                .neighbour_offsets()
+                .map(|(dx, dy)| tiles.at(x + dx, y + dy));
+            let (sprite_index, rotation) =
+                WallPart::determine_sprite_for_wall_part(one, two, three)?;
+            Some((part, kind.sprite(sprite_index), part.rotation() + rotation))
+        })
+        .collect()
+}
+
+/// Determine the sprites for a wall depending on the sprites around it and its kind and return
+/// the entity of the wall. If the walls are baked, the wall itself has no sprites.
 pub fn spawn_sprites_for_wall(
     commands: &mut Commands,
     ascii: &Res<AsciiSheet>,
     tiles: &TileMap,
+    kind: WallKind,
+    rendering: WallRendering,
     x: i32,
     y: i32,
-) {
+) -> Entity {
     let layout = ascii.layout.clone();
     let texture = ascii.image.clone();
+    let (lighthouse_color, color) = wall_colors(tiles);
 
-    commands
-        .spawn(WallTile)
-        .insert((
-            SpriteBundle {
-                transform: Transform {
-                    translation: Vec3 {
-                        x: x as f32,
-                        y: y as f32,
-                        z: 1.0,
-                    } + GENERAL_OFFSET,
-                    ..default()
-                },
-                sprite: Sprite {
-                    anchor: Anchor::BottomLeft,
-                    custom_size: Some(Vec2::splat(1.0)),
-                    ..default()
-                },
-                texture: texture.clone(),
-                ..default()
-            },
-            TextureAtlas {
-                layout: layout.clone(),
-                index: SpriteIndices::Empty.into(),
-            },
-        ))
+    let mut wall = commands.spawn(WallTile);
+    wall.insert(GridPosition::new(x, y))
         .insert(LighthouseBundle {
-            position: LighthousePosition {
-                x: x as usize,
-                y: y as usize,
-                z: 0,
-            },
-            color: LighthouseColor::Inline(0, 0, 255),
-        })
-        .with_children(|parent| {
-            // top left
-            if let Some((sprite_index, rotation)) = WallPart::determine_sprite_for_wall_part(
-                tiles.at(x - 1, y),
-                tiles.at(x - 1, y + 1),
-                tiles.at(x, y + 1),
-            ) {
-                let sprite = Sprite {
-                    custom_size: Some(Vec2::splat(0.5)),
-                    ..default()
-                };
-                let atlas = TextureAtlas {
-                    layout: layout.clone(),
-                    index: sprite_index.into(),
-                };
-
-                parent.spawn(WallPart::TopLeft).insert((
-                    SpriteBundle {
-                        sprite,
-                        transform: Transform {
-                            translation: Vec3 {
-                                y: 0.5,
-                                z: 1.0,
-                                ..default()
-                            },
-                            scale: Vec3::new(1.0, 1.0, 0.0),
-                            rotation: Quat::from_rotation_z(rotation),
-                        },
-                        texture: texture.clone(),
-                        ..default()
-                    },
-                    atlas,
-                ));
-            }
-
-            // top right
-            if let Some((sprite_index, rotation)) = WallPart::determine_sprite_for_wall_part(
-                tiles.at(x, y + 1),
-                tiles.at(x + 1, y + 1),
-                tiles.at(x + 1, y),
-            ) {
-                let sprite = Sprite {
-                    custom_size: Some(Vec2::splat(0.5)),
-                    ..default()
-                };
-                let atlas = TextureAtlas {
-                    layout: layout.clone(),
-                    index: sprite_index.into(),
-                };
-
-                parent.spawn(WallPart::TopRight).insert((
-                    SpriteBundle {
-                        sprite,
-                        transform: Transform {
-                            translation: Vec3 {
-                                x: 0.5,
-                                y: 0.5,
-                                z: 1.0,
-                            },
-                            scale: Vec3::new(1.0, 1.0, 0.0),
-                            rotation: Quat::from_rotation_z(-FRAC_PI_2 + rotation),
-                        },
-                        texture: texture.clone(),
-                        ..default()
-                    },
-                    atlas,
-                ));
-            }
-
-            // bottom right
-            if let Some((sprite_index, rotation)) = WallPart::determine_sprite_for_wall_part(
-                tiles.at(x + 1, y),
-                tiles.at(x + 1, y - 1),
-                tiles.at(x, y - 1),
-            ) {
-                let sprite = Sprite {
-                    custom_size: Some(Vec2::splat(0.5)),
-                    ..default()
-                };
+            position: LighthousePosition { x: 0, y: 0, z: 0 },
+            color: lighthouse_color,
+        });
 
-                let atlas = TextureAtlas {
-                    layout: layout.clone(),
-                    index: sprite_index.into(),
-                };
+    if rendering == WallRendering::Baked {
+        return wall.id();
+    }
 
-                parent.spawn(WallPart::BottomRight).insert((
-                    SpriteBundle {
-                        sprite,
-                        transform: Transform {
-                            translation: Vec3 {
-                                x: 0.5,
-                                z: 1.0,
-                                ..default()
-                            },
-                            scale: Vec3::new(1.0, 1.0, 0.0),
-                            rotation: Quat::from_rotation_z(PI + rotation),
-                        },
-                        texture: texture.clone(),
-                        ..default()
+    wall.insert((
+        SpriteBundle {
+            transform: Transform {
+                // x and y are derived from the grid position
+                translation: Vec3::new(0.0, 0.0, 1.0),
+                ..default()
+            },
+            sprite: Sprite {
+                anchor: Anchor::BottomLeft,
+                custom_size: Some(Vec2::splat(1.0)),
+                ..default()
+            },
+            texture: texture.clone(),
+            ..default()
+        },
+        TextureAtlas {
+            layout: layout.clone(),
+            index: ascii.index(SpriteIndices::Empty),
+        },
+    ))
+    .with_children(|parent| {
+        for (part, sprite_index, rotation) in wall_part_sprites(tiles, kind, x, y) {
+            let sprite = Sprite {
+                color,
+                custom_size: Some(Vec2::splat(0.5)),
+                ..default()
+            };
+            let atlas = TextureAtlas {
+                layout: layout.clone(),
+                index: ascii.index(sprite_index),
+            };
+
+            parent.spawn(part).insert((
+                SpriteBundle {
+                    sprite,
+                    transform: Transform {
+                        translation: part.offset().extend(1.0),
+                        scale: Vec3::new(1.0, 1.0, 0.0),
+                        rotation: Quat::from_rotation_z(rotation),
                     },
-                    atlas,
-                ));
-            }
-
-            // bottom left
-            if let Some((sprite_index, rotation)) = WallPart::determine_sprite_for_wall_part(
-                tiles.at(x, y - 1),
-                tiles.at(x - 1, y - 1),
-                tiles.at(x - 1, y),
-            ) {
-                let sprite = Sprite {
-                    custom_size: Some(Vec2::splat(0.5)),
+                    texture: texture.clone(),
                     ..default()
-                };
-
-                let atlas = TextureAtlas {
-                    layout: layout.clone(),
-                    index: sprite_index.into(),
-                };
-                parent.spawn(WallPart::BottomLeft).insert((
-                    SpriteBundle {
-                        sprite,
-                        transform: Transform {
-                            translation: Vec3 {
-                                z: 1.0,
-                                ..default()
-                            },
-                            scale: Vec3::new(1.0, 1.0, 0.0),
-                            rotation: Quat::from_rotation_z(FRAC_PI_2 + rotation),
-                        },
-                        texture,
-                        ..default()
-                    },
-                    atlas,
-                ));
-            }
-        });
+                },
+                atlas,
+            ));
+        }
+    })
+    .id()
 }
@@ -0,0 +1,121 @@
+use std::collections::VecDeque;
+
+use crate::{
+    ascii::SpriteIndices,
+    map::{Tile, TileMap},
+};
+
+/// The different kinds of walls, which are drawn with different sprites (like in the arcade).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WallKind {
+    /// Walls connected to the border of the map, drawn with a double line.
+    Outer,
+    /// Blocks inside of the maze, drawn with a single line.
+    Inner,
+    /// Walls around the ghost house, drawn with a thin line and square corners.
+    GhostHouse,
+}
+
+impl WallKind {
+    /// Determine the kind of every wall of the map. The result is indexed by `[x][y]` and is
+    /// `None` for tiles, which are no walls.
+    ///
+    /// Walls touching the ghost house (including diagonally) belong to the ghost house. All other
+    /// walls connected to the border of the map (through other walls) are outer walls.
+    pub fn classify(map: &TileMap) -> Vec<Vec<Option<WallKind>>> {
+        let width = map.width() as i32;
+        let height = map.height() as i32;
+        let mut kinds = map
+            .columns()
+            .map(|column| {
+                column
+                    .iter()
+                    .map(|tile| (*tile == Tile::Wall).then_some(WallKind::Inner))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        for position in &map.spawns().ghost_house {
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    let (x, y) = (position.x as i32 + dx, position.y as i32 + dy);
+                    if map.at(x, y) == Some(Tile::Wall) {
+                        kinds[x as usize][y as usize] = Some(WallKind::GhostHouse);
+                    }
+                }
+            }
+        }
+
+        // flood fill all walls connected to the border
+        let mut queue = map
+            .iter()
+            .map(|(position, _)| position)
+            .filter(|position| {
+                let (x, y) = (position.x as i32, position.y as i32);
+                x == 0 || y == 0 || x == width - 1 || y == height - 1
+            })
+            .collect::<VecDeque<_>>();
+
+        while let Some(position) = queue.pop_front() {
+            let kind = &mut kinds[position.x as usize][position.y as usize];
+            if *kind != Some(WallKind::Inner) {
+                continue;
+            }
+
+            *kind = Some(WallKind::Outer);
+            queue.extend(map.neighbours(position, false).map(|(next, _)| next));
+        }
+
+        kinds
+    }
+
+    /// Get the sprite of this kind of wall for a sprite determined by
+    /// [`WallPart::determine_sprite_for_wall_part`](super::WallPart::determine_sprite_for_wall_part).
+    pub fn sprite(self, sprite: SpriteIndices) -> SpriteIndices {
+        match (self, sprite) {
+            (WallKind::Outer, SpriteIndices::WallCorner) => SpriteIndices::DoubleWallCorner,
+            (WallKind::Outer, SpriteIndices::WallStraight) => SpriteIndices::DoubleWallStraight,
+            (WallKind::GhostHouse, SpriteIndices::WallCorner) => SpriteIndices::HouseWallCorner,
+            (WallKind::GhostHouse, SpriteIndices::WallStraight) => SpriteIndices::HouseWallStraight,
+            (_, sprite) => sprite,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ascii::SpriteIndices,
+        map::{TileMap, WallKind},
+    };
+
+    #[test]
+    fn test_classify_walls() {
+        let map = "########\n#......#\n#.##...#\n#......#\n#...H###\n#@.....#\n########"
+            .parse::<TileMap>()
+            .unwrap();
+        let kinds = WallKind::classify(&map);
+
+        assert_eq!(kinds[0][0], Some(WallKind::Outer));
+        assert_eq!(kinds[6][2], Some(WallKind::Outer));
+        assert_eq!(kinds[5][2], Some(WallKind::GhostHouse));
+        assert_eq!(kinds[2][4], Some(WallKind::Inner));
+        assert_eq!(kinds[1][1], None);
+    }
+
+    #[test]
+    fn test_sprites() {
+        assert_eq!(
+            WallKind::Outer.sprite(SpriteIndices::WallCorner),
+            SpriteIndices::DoubleWallCorner
+        );
+        assert_eq!(
+            WallKind::GhostHouse.sprite(SpriteIndices::WallStraight),
+            SpriteIndices::HouseWallStraight
+        );
+        assert_eq!(
+            WallKind::Inner.sprite(SpriteIndices::WallCorner),
+            SpriteIndices::WallCorner
+        );
+    }
+}
@@ -5,7 +5,7 @@ use bevy::prelude::*;
 use crate::{ascii::SpriteIndices, map::Tile};
 
 /// Component representing a specific part of a wall.
-#[derive(Component)]
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum WallPart {
     TopLeft,
     TopRight,
@@ -14,6 +14,25 @@ pub enum WallPart {
 }
 
 impl WallPart {
+    /// All parts of a wall, in clockwise order.
+    pub const ALL: [WallPart; 4] = [
+        WallPart::TopLeft,
+        WallPart::TopRight,
+        WallPart::BottomRight,
+        WallPart::BottomLeft,
+    ];
+
+    /// Offsets of the neighbours `1`, `2` and `3` of this part relative to its wall tile (see
+    /// [`WallPart::determine_sprite_for_wall_part`]).
+    pub fn neighbour_offsets(self) -> [(i32, i32); 3] {
+        match self {
+            WallPart::TopLeft => [(-1, 0), (-1, 1), (0, 1)],
+            WallPart::TopRight => [(0, 1), (1, 1), (1, 0)],
+            WallPart::BottomRight => [(1, 0), (1, -1), (0, -1)],
+            WallPart::BottomLeft => [(0, -1), (-1, -1), (-1, 0)],
+        }
+    }
+
     /// Determine the correct sprite for a given wall part.
     /// The orientation is supposed to be the following (here for the top-left part):
     ///
@@ -28,64 +47,43 @@ impl WallPart {
     /// The result is then either `None`, if there should be no sprite displayed in this sub part of the
     /// tile, or `Some((spriteIndex, rotation))`, where `spriteIndex` is the index of the sprite on the
     /// sprite sheet and `rotation` is the _additional_ rotation for this sheet to correctly align it.
+    ///
+    /// Neighbours outside of the map (`None`) are treated like walls, so walls at the border of
+    /// the map only get an edge towards the inside of the map.
     pub fn determine_sprite_for_wall_part(
         one: Option<Tile>,
         two: Option<Tile>,
         three: Option<Tile>,
     ) -> Option<(SpriteIndices, f32)> {
-        match one {
-            Some(Tile::Wall) => match two {
-                Some(Tile::Wall) => match three {
-                    Some(Tile::Wall) => None,
-                    Some(_) => Some((SpriteIndices::WallStraight, FRAC_PI_2)),
-                    _ => {
-                        warn!("{one:?} {two:?} {three:?} not yet implemented!");
-                        None
-                    }
-                },
-                Some(_) => match three {
-                    Some(Tile::Wall) => Some((SpriteIndices::WallCorner, PI)),
-                    Some(_) => Some((SpriteIndices::WallStraight, FRAC_PI_2)),
-                    _ => {
-                        warn!("{one:?} {two:?} {three:?} not yet implemented!");
-                        None
-                    }
-                },
-                _ => {
-                    warn!("{one:?} {two:?} {three:?} not yet implemented!");
-                    None
-                }
-            },
-            Some(_) => match two {
-                Some(Tile::Wall) => match three {
-                    Some(Tile::Wall) => Some((SpriteIndices::WallStraight, 0.0)),
-                    Some(_) => Some((SpriteIndices::WallCorner, 0.0)),
-                    _ => {
-                        warn!("{one:?} {two:?} {three:?} not yet implemented!");
-                        None
-                    }
-                },
-                Some(_) => match three {
-                    Some(Tile::Wall) => Some((SpriteIndices::WallStraight, 0.0)),
-                    Some(_) => Some((SpriteIndices::WallCorner, 0.0)),
-                    _ => {
-                        warn!("{one:?} {two:?} {three:?} not yet implemented!");
-                        None
-                    }
-                },
-                None => Some((SpriteIndices::WallStraight, 0.0)),
-            },
-            None => match two {
-                None => match three {
-                    Some(Tile::Wall) => None,
-                    Some(_) => Some((SpriteIndices::WallStraight, FRAC_PI_2)),
-                    None => None,
-                },
-                _ => {
-                    warn!("{one:?} {two:?} {three:?} not yet implemented!");
-                    None
-                }
-            },
+        let is_wall = |tile: Option<Tile>| tile.is_none_or(|tile| tile == Tile::Wall);
+
+        match (is_wall(one), is_wall(two), is_wall(three)) {
+            (true, true, true) => None,
+            (true, false, true) => Some((SpriteIndices::WallCorner, PI)),
+            (true, _, false) => Some((SpriteIndices::WallStraight, FRAC_PI_2)),
+            (false, _, true) => Some((SpriteIndices::WallStraight, 0.0)),
+            (false, _, false) => Some((SpriteIndices::WallCorner, 0.0)),
+        }
+    }
+
+    /// Position of the center of this part relative to the bottom left corner of its wall tile.
+    pub fn offset(self) -> Vec2 {
+        match self {
+            WallPart::TopLeft => Vec2::new(0.25, 0.75),
+            WallPart::TopRight => Vec2::new(0.75, 0.75),
+            WallPart::BottomRight => Vec2::new(0.75, 0.25),
+            WallPart::BottomLeft => Vec2::new(0.25, 0.25),
+        }
+    }
+
+    /// Rotation of the sprites of this part, so the orientation of its neighbours matches the one
+    /// of the top-left part.
+    pub fn rotation(self) -> f32 {
+        match self {
+            WallPart::TopLeft => 0.0,
+            WallPart::TopRight => -FRAC_PI_2,
+            WallPart::BottomRight => PI,
+            WallPart::BottomLeft => FRAC_PI_2,
         }
     }
 }
@@ -204,4 +202,81 @@ mod tests {
             None
         );
     }
+
+    #[test]
+    fn test_all_neighbourhoods() {
+        // each tile together with whether it counts as a wall, tiles outside of the map do
+        let tiles = [
+            (None, true),
+            (Some(Tile::Wall), true),
+            (Some(Tile::Empty), false),
+            (Some(Tile::Coin), false),
+        ];
+        let straight = SpriteIndices::WallStraight;
+        let corner = SpriteIndices::WallCorner;
+
+        // (1 is wall, 2 is wall, 3 is wall) => expected sprite
+        let table = [
+            ((true, true, true), None),
+            ((true, true, false), Some((straight, FRAC_PI_2))),
+            ((true, false, true), Some((corner, PI))),
+            ((true, false, false), Some((straight, FRAC_PI_2))),
+            ((false, true, true), Some((straight, 0.0))),
+            ((false, true, false), Some((corner, 0.0))),
+            ((false, false, true), Some((straight, 0.0))),
+            ((false, false, false), Some((corner, 0.0))),
+        ];
+
+        let mut checked = 0;
+        for (one, one_wall) in tiles {
+            for (two, two_wall) in tiles {
+                for (three, three_wall) in tiles {
+                    let (_, expected) = table
+                        .iter()
+                        .find(|(walls, _)| *walls == (one_wall, two_wall, three_wall))
+                        .unwrap();
+
+                    assert_eq!(
+                        WallPart::determine_sprite_for_wall_part(one, two, three),
+                        *expected,
+                        "{one:?} {two:?} {three:?}"
+                    );
+                    checked += 1;
+                }
+            }
+        }
+        assert_eq!(checked, 64);
+    }
+
+    #[test]
+    fn test_neighbourhoods() {
+        let wall = Some(Tile::Wall);
+        let coin = Some(Tile::Coin);
+        let door = Some(Tile::Door);
+        let tunnel = Some(Tile::Tunnel);
+        let straight = SpriteIndices::WallStraight;
+        let corner = SpriteIndices::WallCorner;
+
+        // (1, 2, 3) => expected sprite, with tiles outside of the map (None) behaving like walls
+        let cases = [
+            ((None, None, None), None),
+            ((wall, None, wall), None),
+            ((None, coin, None), Some((corner, PI))),
+            ((wall, door, None), Some((corner, PI))),
+            ((None, wall, coin), Some((straight, FRAC_PI_2))),
+            ((wall, tunnel, door), Some((straight, FRAC_PI_2))),
+            ((coin, None, wall), Some((straight, 0.0))),
+            ((tunnel, coin, None), Some((straight, 0.0))),
+            ((door, None, coin), Some((corner, 0.0))),
+            ((coin, door, tunnel), Some((corner, 0.0))),
+        ];
+
+        for ((one, two, three), expected) in cases {
+            assert_eq!(
+                WallPart::determine_sprite_for_wall_part(one, two, three),
+                expected,
+                "{one:?} {two:?} {three:?}"
+            );
+        }
+    }
 }
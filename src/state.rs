@@ -0,0 +1,19 @@
+use bevy::prelude::*;
+
+/// The different states the game can be in.
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum GameState {
+    /// The map is still being loaded.
+    #[default]
+    Loading,
+    /// The map is loaded and the game is running.
+    Playing,
+    /// The map is edited in the editor.
+    Editor,
+    /// All coins of the level are eaten, so the next level is started.
+    LevelCleared,
+    /// The last level of the pack is cleared.
+    Victory,
+    /// The map could not be loaded, so there is nothing to play.
+    MapError,
+}
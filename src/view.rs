@@ -1,44 +1,63 @@
-use bevy::{prelude::*, render::camera::ScalingMode};
+use bevy::{prelude::*, render::camera::ScalingMode, window::PrimaryWindow};
+
+use crate::map::TileMap;
 
 const CLEAR_COLOR: Color = Color::srgb(0.2, 0.2, 0.2);
 
 const TILE_SIZE: f32 = 40.0;
 
-// pub const COLUMNS: u32 = 19;
-// pub const ROWS: u32 = 22;
-
-pub const COLUMNS: u32 = 14;
-pub const ROWS: u32 = 14;
-
-pub const SCREEN_WIDTH: f32 = TILE_SIZE * COLUMNS as f32;
-pub const SCREEN_HEIGHT: f32 = TILE_SIZE * ROWS as f32;
-
 /// Struct for configuring the view etc.
 pub struct ViewConfigurationPlugin;
 
 impl Plugin for ViewConfigurationPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(ClearColor(CLEAR_COLOR))
-            .add_systems(Startup, spawn_camera);
+            .add_systems(Startup, spawn_camera)
+            .add_systems(
+                Update,
+                (resize_window, fit_camera).run_if(resource_exists_and_changed::<TileMap>),
+            );
     }
 }
 
-/// spawn 2D camera and align it in the positive-positive quadrant
+/// Resize the primary window so every tile of the loaded map is `TILE_SIZE` pixels wide.
+fn resize_window(map: Res<TileMap>, mut window_query: Query<&mut Window, With<PrimaryWindow>>) {
+    let Ok(mut window) = window_query.get_single_mut() else {
+        return;
+    };
+
+    window.resolution.set(
+        TILE_SIZE * map.width() as f32,
+        TILE_SIZE * map.height() as f32,
+    );
+}
+
+/// spawn 2D camera, it is aligned to the map as soon as the map is loaded
 fn spawn_camera(mut commands: Commands) {
-    let mut camera = Camera2dBundle::default();
+    commands.spawn(Camera2dBundle::default());
+}
 
-    // set scaling (i.e., viewport) to given columns and rows
-    camera.projection.scaling_mode = ScalingMode::Fixed {
-        width: COLUMNS as f32,
-        height: ROWS as f32,
+/// align the camera in the positive-positive quadrant, so it shows exactly the loaded map
+fn fit_camera(
+    map: Res<TileMap>,
+    mut camera_query: Query<(&mut OrthographicProjection, &mut Transform), With<Camera2d>>,
+) {
+    let Ok((mut projection, mut transform)) = camera_query.get_single_mut() else {
+        return;
+    };
+    let columns = map.width() as f32;
+    let rows = map.height() as f32;
+
+    // set scaling (i.e., viewport) to the columns and rows of the map
+    projection.scaling_mode = ScalingMode::Fixed {
+        width: columns,
+        height: rows,
     };
 
     // move camera to upper right
-    camera.transform.translation = Vec3 {
-        x: (COLUMNS as f32) / 2.,
-        y: (ROWS as f32) / 2.,
+    transform.translation = Vec3 {
+        x: columns / 2.,
+        y: rows / 2.,
         z: 1000.,
     };
-
-    commands.spawn(camera);
 }